@@ -0,0 +1,109 @@
+use core::ffi::{VaList, c_double, c_int, c_longlong, c_void};
+
+/// The type of a value taken from an argument list, after the default argument promotions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArgKind {
+    Int,
+    LongLong,
+    Double,
+    Pointer,
+}
+
+/// A value taken from an argument list.
+#[derive(Debug, Clone, Copy)]
+pub enum Arg {
+    Int(c_int),
+    LongLong(c_longlong),
+    Double(c_double),
+    Pointer(*const c_void),
+}
+
+impl Arg {
+    /// Returns the kind of argument the value was read as.
+    pub const fn kind(&self) -> ArgKind {
+        match self {
+            Self::Int(_) => ArgKind::Int,
+            Self::LongLong(_) => ArgKind::LongLong,
+            Self::Double(_) => ArgKind::Double,
+            Self::Pointer(_) => ArgKind::Pointer,
+        }
+    }
+
+    pub fn as_int(self) -> c_int {
+        self.as_long_long() as c_int
+    }
+
+    pub fn as_long_long(self) -> c_longlong {
+        match self {
+            Self::Int(i) => i as c_longlong,
+            Self::LongLong(l) => l,
+            Self::Double(d) => d as c_longlong,
+            Self::Pointer(p) => p as usize as c_longlong,
+        }
+    }
+
+    pub fn as_double(self) -> c_double {
+        match self {
+            Self::Double(d) => d,
+            Self::Int(_) | Self::LongLong(_) | Self::Pointer(_) => self.as_long_long() as c_double,
+        }
+    }
+
+    /// Returns a pointer to the value itself.
+    pub fn value_ptr(&self) -> *const c_void {
+        match self {
+            Self::Int(i) => (i as *const c_int).cast(),
+            Self::LongLong(l) => (l as *const c_longlong).cast(),
+            Self::Double(d) => (d as *const c_double).cast(),
+            Self::Pointer(p) => (p as *const *const c_void).cast(),
+        }
+    }
+
+    pub fn as_ptr(self) -> *const c_void {
+        match self {
+            Self::Pointer(p) => p,
+            Self::Int(_) | Self::LongLong(_) | Self::Double(_) => {
+                self.as_long_long() as usize as *const c_void
+            }
+        }
+    }
+}
+
+/// A source of arguments for the printf and scanf families.
+///
+/// Implemented for [`VaList`] which is what the C entry points use,
+/// and for an iterator over a slice of [`Arg`]s so that formatting can be driven from Rust.
+pub trait ArgSource {
+    /// Takes the next argument, which the format expects to be of the given kind.
+    ///
+    /// # Safety
+    /// The next argument must exist and be of the given kind after the default argument promotions,
+    /// as reading it from a [`VaList`] otherwise is undefined behavior.
+    unsafe fn next_arg(&mut self, kind: ArgKind) -> Arg;
+}
+
+impl<'b, 'f> ArgSource for VaList<'b, 'f> {
+    unsafe fn next_arg(&mut self, kind: ArgKind) -> Arg {
+        // SAFETY: guaranteed by the caller
+        unsafe {
+            match kind {
+                ArgKind::Int => Arg::Int(self.arg()),
+                ArgKind::LongLong => Arg::LongLong(self.arg()),
+                ArgKind::Double => Arg::Double(self.arg()),
+                ArgKind::Pointer => Arg::Pointer(self.arg()),
+            }
+        }
+    }
+}
+
+impl<'a> ArgSource for core::slice::Iter<'a, Arg> {
+    unsafe fn next_arg(&mut self, kind: ArgKind) -> Arg {
+        // Missing arguments read as zeroes instead of garbage
+        self.next().copied().unwrap_or(match kind {
+            ArgKind::Int => Arg::Int(0),
+            ArgKind::LongLong => Arg::LongLong(0),
+            ArgKind::Double => Arg::Double(0.),
+            ArgKind::Pointer => Arg::Pointer(core::ptr::null()),
+        })
+    }
+}
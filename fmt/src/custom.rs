@@ -0,0 +1,166 @@
+//! Custom printf conversion specifiers, see `include/printf.h`.
+
+use core::ffi::{c_char, c_int, c_uchar, c_void};
+
+use alloc::{vec, vec::Vec};
+
+use crate::args::{Arg, ArgKind};
+
+pub const PA_INT: c_int = 0;
+pub const PA_CHAR: c_int = 1;
+pub const PA_WCHAR: c_int = 2;
+pub const PA_STRING: c_int = 3;
+pub const PA_WSTRING: c_int = 4;
+pub const PA_POINTER: c_int = 5;
+pub const PA_FLOAT: c_int = 6;
+pub const PA_DOUBLE: c_int = 7;
+
+pub const PA_FLAG_MASK: c_int = 0xff00;
+pub const PA_FLAG_LONG_LONG: c_int = 1 << 8;
+pub const PA_FLAG_LONG_DOUBLE: c_int = PA_FLAG_LONG_LONG;
+pub const PA_FLAG_LONG: c_int = 1 << 9;
+pub const PA_FLAG_SHORT: c_int = 1 << 10;
+pub const PA_FLAG_PTR: c_int = 1 << 11;
+
+/// The conversion specification passed to custom conversion handlers.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct SafaPrintfInfo {
+    /// The precision, -1 if none was given
+    pub prec: c_int,
+    /// The minimum field width, 0 if none was given
+    pub width: c_int,
+    /// The conversion specifier
+    pub spec: c_int,
+    /// `'0'` if the `0` flag was given, `' '` otherwise
+    pub pad: c_int,
+    pub left: c_uchar,
+    pub showsign: c_uchar,
+    pub space: c_uchar,
+    pub alt: c_uchar,
+    /// `hh`
+    pub is_char: c_uchar,
+    /// `h`
+    pub is_short: c_uchar,
+    /// `l`
+    pub is_long: c_uchar,
+    /// `ll` or `L`
+    pub is_long_double: c_uchar,
+}
+
+/// Writes the conversion snprintf-style into a buffer of `n` bytes,
+/// returns the length of the whole conversion or a negative value on failure.
+pub type SafaPrintfFunction = unsafe extern "C" fn(
+    buf: *mut c_char,
+    n: usize,
+    info: *const SafaPrintfInfo,
+    args: *const *const c_void,
+) -> c_int;
+
+/// Stores the types of the arguments the conversion takes in `argtypes` (up to `n` of them),
+/// returns the amount of arguments the conversion takes.
+pub type SafaPrintfArgInfoFunction = unsafe extern "C" fn(
+    info: *const SafaPrintfInfo,
+    n: usize,
+    argtypes: *mut c_int,
+    size: *mut c_int,
+) -> c_int;
+
+/// A conversion registered with `safa_register_printf_specifier`.
+#[derive(Debug, Clone, Copy)]
+pub struct CustomSpecifier {
+    handler: SafaPrintfFunction,
+    arginfo: SafaPrintfArgInfoFunction,
+}
+
+impl CustomSpecifier {
+    /// # Safety
+    /// `handler` and `arginfo` must behave as described in `include/printf.h`,
+    /// they are called with the arguments of every conversion they are used for.
+    pub const unsafe fn new(
+        handler: SafaPrintfFunction,
+        arginfo: SafaPrintfArgInfoFunction,
+    ) -> Self {
+        Self { handler, arginfo }
+    }
+
+    /// Returns the kinds of the arguments the conversion described by `info` takes,
+    /// `None` if `arginfo` fails or asks for a type that isn't supported.
+    pub(crate) fn arg_kinds(&self, info: &SafaPrintfInfo) -> Option<Vec<ArgKind>> {
+        let mut argtypes = vec![0; 8];
+        let mut size = 0;
+
+        loop {
+            // SAFETY: guaranteed by the caller of `Self::new`
+            let amount =
+                unsafe { (self.arginfo)(info, argtypes.len(), argtypes.as_mut_ptr(), &mut size) };
+            let amount = usize::try_from(amount).ok()?;
+            if amount <= argtypes.len() {
+                argtypes.truncate(amount);
+                break;
+            }
+            argtypes.resize(amount, 0);
+        }
+
+        argtypes
+            .into_iter()
+            .map(|ty| {
+                if ty & PA_FLAG_PTR != 0 {
+                    return Some(ArgKind::Pointer);
+                }
+
+                Some(match ty & !PA_FLAG_MASK {
+                    PA_INT if ty & (PA_FLAG_LONG | PA_FLAG_LONG_LONG) != 0 => ArgKind::LongLong,
+                    PA_INT | PA_CHAR | PA_WCHAR => ArgKind::Int,
+                    PA_STRING | PA_WSTRING | PA_POINTER => ArgKind::Pointer,
+                    // `long double` isn't supported, rather than being read as a `double`
+                    PA_DOUBLE if ty & PA_FLAG_LONG_DOUBLE != 0 => return None,
+                    PA_FLOAT | PA_DOUBLE => ArgKind::Double,
+                    // user defined types aren't supported
+                    _ => return None,
+                })
+            })
+            .collect()
+    }
+
+    /// Formats `args` with the handler, returns the formatted bytes or `None` if the handler fails.
+    pub(crate) fn format(&self, info: &SafaPrintfInfo, args: &[Arg]) -> Option<Vec<u8>> {
+        let args: Vec<*const c_void> = args.iter().map(Arg::value_ptr).collect();
+
+        let mut buf = vec![0u8; 64];
+        loop {
+            // SAFETY: guaranteed by the caller of `Self::new`
+            let len =
+                unsafe { (self.handler)(buf.as_mut_ptr().cast(), buf.len(), info, args.as_ptr()) };
+            let len = usize::try_from(len).ok()?;
+
+            // One more byte for the NUL the handler may write
+            if len < buf.len() {
+                buf.truncate(len);
+                return Some(buf);
+            }
+            buf.resize(len + 1, 0);
+        }
+    }
+}
+
+/// Where the printf family looks up custom conversions before the standard ones.
+pub trait CustomSpecifiers {
+    /// Returns the custom conversion for the conversion specifier `spec` if any.
+    fn find(&self, spec: u8) -> Option<CustomSpecifier>;
+}
+
+/// No custom conversions at all.
+impl CustomSpecifiers for () {
+    fn find(&self, _: u8) -> Option<CustomSpecifier> {
+        None
+    }
+}
+
+impl CustomSpecifiers for Vec<(u8, CustomSpecifier)> {
+    fn find(&self, spec: u8) -> Option<CustomSpecifier> {
+        self.iter()
+            .find(|(s, _)| *s == spec)
+            .map(|(_, custom)| *custom)
+    }
+}
@@ -0,0 +1,1129 @@
+use core::convert::Infallible;
+use core::ffi::{CStr, c_char, c_int, c_longlong, c_schar, c_short, c_uint};
+use core::ffi::{c_uchar, c_ushort};
+use core::fmt::Write;
+
+use alloc::collections::TryReserveError;
+use alloc::vec::Vec;
+
+use crate::Error;
+use crate::args::{Arg, ArgKind, ArgSource};
+use crate::custom::{CustomSpecifier, CustomSpecifiers, SafaPrintfInfo};
+
+/// The highest argument number a conversion can refer to with `%n$`, see `include/limits.h`.
+pub const NL_ARGMAX: usize = 4096;
+
+/// A writer over a fixed buffer, bytes past the end of the buffer are counted as written but discarded.
+#[derive(Debug)]
+pub struct BufWriter<'a>(&'a mut [u8], usize);
+impl<'a> BufWriter<'a> {
+    pub const fn new(b: &'a mut [u8]) -> Self {
+        Self(b, 0)
+    }
+
+    /// Returns the amount of bytes actually stored in the buffer.
+    pub const fn stored(&self) -> usize {
+        self.1
+    }
+}
+
+pub trait CWriter {
+    type Error;
+    fn write_bytes(&mut self, bytes: &[u8]) -> Result<usize, Self::Error>;
+    fn write_byte(&mut self, b: u8) -> Result<usize, Self::Error> {
+        self.write_bytes(&[b])
+    }
+}
+
+impl<'a> CWriter for BufWriter<'a> {
+    type Error = Infallible;
+    fn write_bytes(&mut self, bytes: &[u8]) -> Result<usize, Infallible> {
+        if self.0.len() <= self.1 {
+            return Ok(bytes.len());
+        }
+
+        let to = &mut self.0[self.1..];
+        let amount = to.len().min(bytes.len());
+        to[..amount].copy_from_slice(&bytes[..amount]);
+        self.1 += amount;
+
+        Ok(bytes.len())
+    }
+}
+
+/// A writer that grows as needed.
+impl CWriter for Vec<u8> {
+    type Error = TryReserveError;
+    fn write_bytes(&mut self, bytes: &[u8]) -> Result<usize, TryReserveError> {
+        self.try_reserve(bytes.len())?;
+        self.extend_from_slice(bytes);
+        Ok(bytes.len())
+    }
+}
+
+impl<'a> Write for BufWriter<'a> {
+    fn write_str(&mut self, s: &str) -> core::fmt::Result {
+        match self.write_bytes(s.as_bytes()) {
+            Ok(_) => Ok(()),
+            Err(e) => match e {},
+        }
+    }
+}
+
+/// Formats a whole format string, only created by [`printf_to`] whose safety requirements it relies on.
+struct CPrinter<'a, 'fmt, W: CWriter, A: ArgSource> {
+    writer: &'a mut W,
+    wrote: usize,
+    cursor: FmtCursor<'fmt>,
+    args: A,
+    specifiers: &'a dyn CustomSpecifiers,
+    /// Every argument read up front if the format uses numbered arguments (`%n$`)
+    numbered_args: Option<Vec<Arg>>,
+}
+
+impl<'a, 'fmt, T: CWriter, A: ArgSource> CWriter for CPrinter<'a, 'fmt, T, A> {
+    type Error = Error<T::Error>;
+    /// Writes all of `bytes`, retrying short writes.
+    fn write_bytes(&mut self, bytes: &[u8]) -> Result<usize, Self::Error> {
+        let mut left = bytes;
+        while !left.is_empty() {
+            let am = self.writer.write_bytes(left).map_err(Error::Io)?;
+            if am == 0 {
+                return Err(Error::WriteZero);
+            }
+            left = &left[am..];
+        }
+
+        self.wrote += bytes.len();
+        Ok(bytes.len())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum LengthModifier {
+    None,
+    /// hh
+    Char,
+    /// h
+    Short,
+    /// l
+    Long,
+    /// ll
+    LongLong,
+    /// L
+    LongDouble,
+    /// z
+    SizeT,
+    /// t
+    PtrDiffT,
+    /// j
+    MaxT,
+}
+
+impl LengthModifier {
+    /// Returns the modifier matching the C23 `wN` length (or `wfN` if `fast` is set),
+    /// that is the one for `intN_t` (or `int_fastN_t`, as defined by `include/inttypes.h`).
+    pub(crate) const fn from_width(bits: usize, fast: bool) -> Option<Self> {
+        Some(match (bits, fast) {
+            (8, _) => Self::Char,
+            (16, false) => Self::Short,
+            (16, true) | (32, _) => Self::None,
+            (64, _) => Self::LongLong,
+            _ => return None,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Binary,
+    BigBinary,
+    Octal,
+    Hex,
+    BigHex,
+    Normal,
+}
+
+impl Kind {
+    const fn radix(self) -> u64 {
+        match self {
+            Self::Binary | Self::BigBinary => 2,
+            Self::Octal => 8,
+            Self::Hex | Self::BigHex => 16,
+            Self::Normal => 10,
+        }
+    }
+
+    const fn digits(self) -> &'static [u8; 16] {
+        match self {
+            Self::BigHex => b"0123456789ABCDEF",
+            _ => b"0123456789abcdef",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FloatKind {
+    /// `%f`
+    Fixed,
+    /// `%e`
+    Exponent,
+    /// `%g`
+    General,
+    /// `%a`
+    Hex,
+}
+
+/// Formats the magnitude of a finite floating point number as C does,
+/// the sign and radix prefix are left for the caller.
+///
+/// The decimal digits themselves come from [`core::fmt`] which rounds the exact binary value correctly,
+/// this only rearranges them into the C forms.
+struct FloatBuf(Vec<u8>);
+
+impl Write for FloatBuf {
+    fn write_str(&mut self, s: &str) -> core::fmt::Result {
+        self.0.extend_from_slice(s.as_bytes());
+        Ok(())
+    }
+}
+
+impl FloatBuf {
+    /// Returns the decimal exponent of `value` once rounded to `precision` digits after the point.
+    fn decimal_exponent(value: f64, precision: usize) -> i32 {
+        let mut buf = FloatBuf(Vec::new());
+        _ = write!(buf, "{:.precision$e}", value);
+        buf.split_exponent(0)
+    }
+
+    /// Removes the Rust style exponent that starts after `start` and returns it.
+    fn split_exponent(&mut self, start: usize) -> i32 {
+        let Some(e) = self.0[start..].iter().position(|c| *c == b'e') else {
+            return 0;
+        };
+        let e = start + e;
+
+        let exponent = str::from_utf8(&self.0[e + 1..])
+            .ok()
+            .and_then(|e| e.parse().ok())
+            .unwrap_or(0);
+        self.0.truncate(e);
+        exponent
+    }
+
+    /// Writes a C style exponent, that is a sign followed by at least `min_digits` digits.
+    fn write_exponent_suffix(&mut self, letter: u8, exponent: i32, min_digits: usize) {
+        self.0.push(letter);
+        self.0.push(if exponent < 0 { b'-' } else { b'+' });
+        _ = write!(self, "{:0min_digits$}", exponent.unsigned_abs());
+    }
+
+    /// `%f`
+    fn write_fixed(&mut self, value: f64, precision: usize, alternate: bool) {
+        _ = write!(self, "{:.precision$}", value);
+        if alternate && precision == 0 {
+            self.0.push(b'.');
+        }
+    }
+
+    /// `%e`
+    fn write_exponent(&mut self, value: f64, precision: usize, alternate: bool, upper: bool) {
+        let start = self.0.len();
+        _ = write!(self, "{:.precision$e}", value);
+        let exponent = self.split_exponent(start);
+
+        if alternate && precision == 0 {
+            self.0.push(b'.');
+        }
+        self.write_exponent_suffix(if upper { b'E' } else { b'e' }, exponent, 2);
+    }
+
+    /// `%g`, uses `%e` for very small or large exponents and `%f` otherwise, with the trailing zeroes removed.
+    fn write_general(&mut self, value: f64, precision: usize, alternate: bool, upper: bool) {
+        let precision = precision.max(1);
+        let exponent = Self::decimal_exponent(value, precision - 1);
+
+        let start = self.0.len();
+        if exponent < -4 || exponent >= precision as i32 {
+            self.write_exponent(value, precision - 1, alternate, upper);
+        } else {
+            let precision = (precision as i32 - 1 - exponent) as usize;
+            self.write_fixed(value, precision, alternate);
+        }
+
+        if alternate {
+            return;
+        }
+
+        let mantissa_end = self.0[start..]
+            .iter()
+            .position(|c| *c == b'e' || *c == b'E')
+            .map_or(self.0.len(), |e| start + e);
+        let mantissa = &self.0[start..mantissa_end];
+        if !mantissa.contains(&b'.') {
+            return;
+        }
+
+        let mut end = mantissa_end;
+        while self.0[end - 1] == b'0' {
+            end -= 1;
+        }
+        if self.0[end - 1] == b'.' {
+            end -= 1;
+        }
+        self.0.drain(end..mantissa_end);
+    }
+
+    /// `%a`, without the `0x` prefix.
+    fn write_hex(&mut self, value: f64, precision: Option<usize>, alternate: bool, upper: bool) {
+        const MANTISSA_BITS: u32 = 52;
+        const MANTISSA_DIGITS: usize = (MANTISSA_BITS / 4) as usize;
+
+        let bits = value.to_bits();
+        let biased_exponent = ((bits >> MANTISSA_BITS) & 0x7ff) as i32;
+        let fraction = bits & ((1 << MANTISSA_BITS) - 1);
+
+        // subnormals are printed as `0x0.xxxp-1022` rather than normalized
+        let (lead, exponent) = match (biased_exponent, fraction) {
+            (0, 0) => (0, 0),
+            (0, _) => (0, -1022),
+            (e, _) => (1, e - 1023),
+        };
+        let mut mantissa = (lead << MANTISSA_BITS) | fraction;
+
+        let digits = match precision {
+            Some(precision) if precision < MANTISSA_DIGITS => {
+                // rounds to nearest, ties to even
+                let shift = (MANTISSA_DIGITS - precision) as u32 * 4;
+                let rest = mantissa & ((1 << shift) - 1);
+                let half = 1 << (shift - 1);
+
+                mantissa >>= shift;
+                if rest > half || (rest == half && mantissa & 1 == 1) {
+                    mantissa += 1;
+                }
+                precision
+            }
+            Some(_) => MANTISSA_DIGITS,
+            None => {
+                let trailing = (fraction.trailing_zeros() / 4) as usize;
+                let digits = MANTISSA_DIGITS.saturating_sub(trailing);
+                mantissa >>= (MANTISSA_DIGITS - digits) as u32 * 4;
+                digits
+            }
+        };
+
+        let lead = mantissa >> (digits as u32 * 4);
+        let fraction = mantissa & ((1 << (digits as u32 * 4)) - 1);
+        let extra_zeroes = precision.map_or(0, |p| p.saturating_sub(MANTISSA_DIGITS));
+
+        _ = write!(self, "{lead}");
+        if digits != 0 || extra_zeroes != 0 || alternate {
+            self.0.push(b'.');
+        }
+
+        if digits != 0 {
+            if upper {
+                _ = write!(self, "{:0digits$X}", fraction);
+            } else {
+                _ = write!(self, "{:0digits$x}", fraction);
+            }
+        }
+        self.0.resize(self.0.len() + extra_zeroes, b'0');
+        self.write_exponent_suffix(if upper { b'P' } else { b'p' }, exponent, 1);
+    }
+}
+
+/// A parsed conversion specification, that is everything between the `%` and the conversion specifier.
+#[derive(Debug, Clone, Copy)]
+struct ConversionSpec {
+    /// `-` flag, pads on the right instead of the left
+    left_justify: bool,
+    /// `+` flag, always prints the sign of signed conversions
+    force_sign: bool,
+    /// ` ` flag, prints a space in place of a `+` sign
+    space_sign: bool,
+    /// `#` flag, the alternative form
+    alternate: bool,
+    /// `0` flag, pads numeric conversions with zeroes instead of spaces
+    zero_pad: bool,
+    /// The argument number given by `%n$`, if any
+    position: Option<usize>,
+    /// The minimum field width
+    width: usize,
+    precision: Option<usize>,
+    length: LengthModifier,
+}
+
+impl ConversionSpec {
+    const fn new() -> Self {
+        Self {
+            left_justify: false,
+            force_sign: false,
+            space_sign: false,
+            alternate: false,
+            zero_pad: false,
+            position: None,
+            width: 0,
+            precision: None,
+            length: LengthModifier::None,
+        }
+    }
+
+    /// Returns the sign to print before a numeric value.
+    const fn sign(&self, negative: bool) -> &'static [u8] {
+        if negative {
+            b"-"
+        } else if self.force_sign {
+            b"+"
+        } else if self.space_sign {
+            b" "
+        } else {
+            b""
+        }
+    }
+
+    /// Returns the specification as passed to custom conversion handlers.
+    fn printf_info(&self, conversion: u8) -> SafaPrintfInfo {
+        SafaPrintfInfo {
+            prec: self
+                .precision
+                .map_or(-1, |prec| c_int::try_from(prec).unwrap_or(c_int::MAX)),
+            width: c_int::try_from(self.width).unwrap_or(c_int::MAX),
+            spec: conversion as c_int,
+            pad: if self.zero_pad { b'0' } else { b' ' } as c_int,
+            left: self.left_justify as c_uchar,
+            showsign: self.force_sign as c_uchar,
+            space: self.space_sign as c_uchar,
+            alt: self.alternate as c_uchar,
+            is_char: (self.length == LengthModifier::Char) as c_uchar,
+            is_short: (self.length == LengthModifier::Short) as c_uchar,
+            is_long: (self.length == LengthModifier::Long) as c_uchar,
+            is_long_double: matches!(
+                self.length,
+                LengthModifier::LongLong | LengthModifier::LongDouble
+            ) as c_uchar,
+        }
+    }
+
+    /// Returns the amount of zeroes the `0` flag requires to fill the field for a value of `len` bytes.
+    const fn zero_fill(&self, len: usize) -> usize {
+        if self.zero_pad && !self.left_justify {
+            self.width.saturating_sub(len)
+        } else {
+            0
+        }
+    }
+}
+
+/// Returns whether or not `conversion` is a floating point conversion with the `L` length modifier,
+/// which fails as `long double` isn't supported.
+const fn is_long_double(conversion: u8, length: LengthModifier) -> bool {
+    matches!(
+        conversion,
+        b'f' | b'F' | b'e' | b'E' | b'g' | b'G' | b'a' | b'A'
+    ) && matches!(length, LengthModifier::LongDouble)
+}
+
+/// Returns the kind of argument consumed by `conversion` with the `length` modifier, if any.
+const fn arg_kind_of(conversion: u8, length: LengthModifier) -> Option<ArgKind> {
+    Some(match conversion {
+        b'd' | b'i' | b'u' | b'b' | b'B' | b'o' | b'x' | b'X' => match length {
+            LengthModifier::None | LengthModifier::Char | LengthModifier::Short => ArgKind::Int,
+            _ => ArgKind::LongLong,
+        },
+        b'c' => ArgKind::Int,
+        b'f' | b'F' | b'e' | b'E' | b'g' | b'G' | b'a' | b'A' => ArgKind::Double,
+        b's' | b'p' => ArgKind::Pointer,
+        _ => return None,
+    })
+}
+
+/// A field width or a precision as written in the format.
+#[derive(Debug, Clone, Copy)]
+enum Count {
+    Given(usize),
+    /// `*`
+    NextArg,
+    /// `*m$`
+    Arg(usize),
+}
+
+/// A conversion specification as written in the format, before the field width and precision arguments are read.
+#[derive(Debug, Clone, Copy)]
+struct ParsedSpec {
+    spec: ConversionSpec,
+    width: Option<Count>,
+    precision: Option<Count>,
+    conversion: Option<u8>,
+}
+
+impl ParsedSpec {
+    /// Returns the specification with its field width and precision,
+    /// `read_count` returns the value of those given as arguments.
+    fn resolve(&self, mut read_count: impl FnMut(Count) -> c_longlong) -> ConversionSpec {
+        let mut spec = self.spec;
+
+        if let Some(width) = self.width {
+            // A negative field width is taken as a `-` flag followed by a positive field width
+            let width = read_count(width);
+            if width < 0 {
+                spec.left_justify = true;
+            }
+            spec.width = width.unsigned_abs() as usize;
+        }
+
+        // A negative precision is taken as if the precision were omitted
+        spec.precision = self
+            .precision
+            .and_then(|precision| usize::try_from(read_count(precision)).ok());
+        spec
+    }
+}
+
+/// A cursor over a format string, shared by the printf and scanf families.
+pub(crate) struct FmtCursor<'fmt> {
+    fmt: &'fmt [u8],
+    curr_index: usize,
+}
+
+impl<'fmt> FmtCursor<'fmt> {
+    pub(crate) const fn new(fmt: &'fmt [u8]) -> Self {
+        Self { fmt, curr_index: 0 }
+    }
+
+    #[inline]
+    pub fn peek(&self) -> Option<u8> {
+        self.fmt.get(self.curr_index).copied()
+    }
+
+    #[inline]
+    pub fn next(&mut self) -> Option<u8> {
+        let byte = self.peek();
+        if byte.is_some() {
+            self.curr_index += 1;
+        }
+        byte
+    }
+
+    pub(crate) fn try_make_length(&mut self) -> Option<LengthModifier> {
+        match self.peek()? {
+            b'h' => {
+                self.next();
+
+                Some(if self.peek() == Some(b'h') {
+                    self.next();
+                    LengthModifier::Char
+                } else {
+                    LengthModifier::Short
+                })
+            }
+            b'l' => {
+                self.next();
+
+                Some(if self.peek() == Some(b'l') {
+                    self.next();
+                    LengthModifier::LongLong
+                } else {
+                    LengthModifier::Long
+                })
+            }
+            b'j' => {
+                self.next();
+                Some(LengthModifier::MaxT)
+            }
+            b'z' => {
+                self.next();
+                Some(LengthModifier::SizeT)
+            }
+            b't' => {
+                self.next();
+                Some(LengthModifier::PtrDiffT)
+            }
+            b'L' => {
+                self.next();
+                Some(LengthModifier::LongDouble)
+            }
+            b'w' => {
+                let start = self.curr_index;
+                self.next();
+                let fast = self.peek() == Some(b'f');
+                if fast {
+                    self.next();
+                }
+
+                let length = self
+                    .try_make_number()
+                    .and_then(|bits| LengthModifier::from_width(bits, fast));
+                // An invalid width leaves the `w` to be printed as an unknown conversion
+                if length.is_none() {
+                    self.curr_index = start;
+                }
+                length
+            }
+            _ => None,
+        }
+    }
+
+    /// Parses a decimal number in the format string, returns `None` if there are no digits.
+    pub(crate) fn try_make_number(&mut self) -> Option<usize> {
+        let mut number = None;
+        while let Some(digit @ b'0'..=b'9') = self.peek() {
+            self.next();
+            let prev: usize = number.unwrap_or(0);
+            number = Some(
+                prev.saturating_mul(10)
+                    .saturating_add((digit - b'0') as usize),
+            );
+        }
+        number
+    }
+
+    /// Parses an argument number followed by a `$`, leaves the cursor untouched if there is none.
+    fn try_make_position(&mut self) -> Option<usize> {
+        let start = self.curr_index;
+        if let Some(position @ 1..) = self.try_make_number()
+            && self.peek() == Some(b'$')
+        {
+            self.next();
+            return Some(position);
+        }
+
+        self.curr_index = start;
+        None
+    }
+
+    fn try_make_count(&mut self) -> Option<Count> {
+        if self.peek() == Some(b'*') {
+            self.next();
+            return Some(match self.try_make_position() {
+                Some(position) => Count::Arg(position),
+                None => Count::NextArg,
+            });
+        }
+
+        self.try_make_number().map(Count::Given)
+    }
+
+    /// Parses a conversion specification, the cursor must be right after the `%`.
+    fn make_spec(&mut self) -> ParsedSpec {
+        let mut spec = ConversionSpec::new();
+        spec.position = self.try_make_position();
+
+        while let Some(flag) = self.peek() {
+            match flag {
+                b'-' => spec.left_justify = true,
+                b'+' => spec.force_sign = true,
+                b' ' => spec.space_sign = true,
+                b'#' => spec.alternate = true,
+                b'0' => spec.zero_pad = true,
+                _ => break,
+            }
+            self.next();
+        }
+
+        let width = self.try_make_count();
+        let precision = if self.peek() == Some(b'.') {
+            self.next();
+            Some(self.try_make_count().unwrap_or(Count::Given(0)))
+        } else {
+            None
+        };
+
+        spec.length = self.try_make_length().unwrap_or(LengthModifier::None);
+        ParsedSpec {
+            spec,
+            width,
+            precision,
+            conversion: self.next(),
+        }
+    }
+}
+
+/// Reads every argument up front if `fmt` uses numbered arguments (`%n$`),
+/// as they may then be consumed in any order, returns `None` otherwise.
+///
+/// Fails with [`Error::InvalidFormat`] if numbered and unnumbered arguments are mixed,
+/// if an argument past [`NL_ARGMAX`] is referred to or if a `long double` is,
+/// or if a custom conversion asks for other arguments once its field width and precision are known.
+///
+/// # Safety
+/// See [`printf_to`].
+unsafe fn read_numbered_args<A: ArgSource, E>(
+    fmt: &[u8],
+    args: &mut A,
+    specifiers: &dyn CustomSpecifiers,
+) -> Result<Option<Vec<Arg>>, Error<E>> {
+    let mut kinds: Vec<Option<ArgKind>> = Vec::new();
+    let mut numbered = false;
+    let mut unnumbered = false;
+    // Custom conversions with field widths or precisions given as arguments, and the kinds they were first given
+    let mut unresolved: Vec<(ParsedSpec, u8, Vec<ArgKind>)> = Vec::new();
+
+    let mut use_arg = |position: Option<usize>, kind: ArgKind| {
+        match position {
+            Some(position @ 1..=NL_ARGMAX) => {
+                numbered = true;
+                if kinds.len() < position {
+                    kinds.resize(position, None);
+                }
+                kinds[position - 1] = Some(kind);
+            }
+            Some(_) => return Err(Error::InvalidFormat),
+            None => unnumbered = true,
+        }
+        Ok(())
+    };
+
+    let mut cursor = FmtCursor::new(fmt);
+    while let Some(byte) = cursor.next() {
+        if byte != b'%' {
+            continue;
+        }
+
+        let parsed = cursor.make_spec();
+        for count in [parsed.width, parsed.precision] {
+            match count {
+                Some(Count::NextArg) => use_arg(None, ArgKind::Int)?,
+                Some(Count::Arg(position)) => use_arg(Some(position), ArgKind::Int)?,
+                Some(Count::Given(_)) | None => {}
+            }
+        }
+
+        let Some(conversion) = parsed.conversion else {
+            continue;
+        };
+
+        if let Some(custom) = specifiers.find(conversion) {
+            // Field widths and precisions given as arguments aren't known yet
+            let mut spec = parsed.spec;
+            if let Some(Count::Given(width)) = parsed.width {
+                spec.width = width;
+            }
+            if let Some(Count::Given(precision)) = parsed.precision {
+                spec.precision = Some(precision);
+            }
+
+            let kinds = custom
+                .arg_kinds(&spec.printf_info(conversion))
+                .ok_or(Error::InvalidFormat)?;
+            for (i, kind) in kinds.iter().enumerate() {
+                use_arg(parsed.spec.position.map(|position| position + i), *kind)?;
+            }
+
+            if matches!(parsed.width, Some(Count::Arg(_)))
+                || matches!(parsed.precision, Some(Count::Arg(_)))
+            {
+                unresolved.push((parsed, conversion, kinds));
+            }
+        } else if is_long_double(conversion, parsed.spec.length) {
+            return Err(Error::InvalidFormat);
+        } else if let Some(kind) = arg_kind_of(conversion, parsed.spec.length) {
+            use_arg(parsed.spec.position, kind)?;
+        }
+    }
+
+    if !numbered {
+        return Ok(None);
+    }
+
+    if unnumbered {
+        return Err(Error::InvalidFormat);
+    }
+
+    // Arguments that aren't referred to are assumed to be `int`s
+    let args: Vec<Arg> = kinds
+        .into_iter()
+        // SAFETY: guaranteed by the caller
+        .map(|kind| unsafe { args.next_arg(kind.unwrap_or(ArgKind::Int)) })
+        .collect();
+
+    // The arguments a custom conversion takes may depend on its field width and precision,
+    // which are only known now, the arguments were already read as the conversion first asked for
+    for (parsed, conversion, first_kinds) in unresolved {
+        let spec = parsed.resolve(|count| match count {
+            Count::Given(count) => count as c_longlong,
+            Count::Arg(position) => args[position - 1].as_int() as c_longlong,
+            // Unnumbered arguments would have failed already
+            Count::NextArg => 0,
+        });
+
+        let kinds = specifiers
+            .find(conversion)
+            .and_then(|custom| custom.arg_kinds(&spec.printf_info(conversion)));
+        if kinds.as_ref() != Some(&first_kinds) {
+            return Err(Error::InvalidFormat);
+        }
+    }
+    Ok(Some(args))
+}
+
+impl<'a, 'fmt, W: CWriter, A: ArgSource> CPrinter<'a, 'fmt, W, A> {
+    /// # Safety
+    /// See [`printf_to`].
+    unsafe fn new(
+        writer: &'a mut W,
+        fmt: &'fmt [u8],
+        mut args: A,
+        specifiers: &'a dyn CustomSpecifiers,
+    ) -> Result<Self, Error<W::Error>> {
+        let numbered_args = unsafe { read_numbered_args(fmt, &mut args, specifiers)? };
+        Ok(Self {
+            writer,
+            wrote: 0,
+            cursor: FmtCursor::new(fmt),
+            args,
+            specifiers,
+            numbered_args,
+        })
+    }
+
+    /// Takes an argument of the given kind, either the argument numbered `position` or the next one.
+    fn arg(&mut self, kind: ArgKind, position: Option<usize>) -> Arg {
+        if let Some(args) = &self.numbered_args
+            && let Some(position) = position
+        {
+            return args[position - 1];
+        }
+
+        // SAFETY: guaranteed by the caller of `printf_to`
+        unsafe { self.args.next_arg(kind) }
+    }
+
+    /// Returns the value of a field width or precision, reading the `int` argument of the `*` forms.
+    fn read_count(&mut self, count: Count) -> c_longlong {
+        match count {
+            Count::Given(count) => count as c_longlong,
+            Count::NextArg => self.arg(ArgKind::Int, None).as_int() as c_longlong,
+            Count::Arg(position) => self.arg(ArgKind::Int, Some(position)).as_int() as c_longlong,
+        }
+    }
+
+    /// Reads the field width and precision arguments of `parsed`, if any.
+    fn resolve_spec(&mut self, parsed: &ParsedSpec) -> ConversionSpec {
+        parsed.resolve(|count| self.read_count(count))
+    }
+
+    fn write_repeated(&mut self, byte: u8, count: usize) -> Result<(), Error<W::Error>> {
+        const CHUNK: usize = 32;
+        let chunk = [byte; CHUNK];
+
+        let mut left = count;
+        while left > 0 {
+            let amount = left.min(CHUNK);
+            self.write_bytes(&chunk[..amount])?;
+            left -= amount;
+        }
+        Ok(())
+    }
+
+    /// Writes `prefix`, `zeroes` zeroes and then `body`, padded with spaces to the field width of `spec`.
+    fn write_padded(
+        &mut self,
+        spec: &ConversionSpec,
+        prefix: &[u8],
+        zeroes: usize,
+        body: &[u8],
+    ) -> Result<(), Error<W::Error>> {
+        let len = prefix.len() + zeroes + body.len();
+        let padding = spec.width.saturating_sub(len);
+
+        if !spec.left_justify {
+            self.write_repeated(b' ', padding)?;
+        }
+
+        self.write_bytes(prefix)?;
+        self.write_repeated(b'0', zeroes)?;
+        self.write_bytes(body)?;
+
+        if spec.left_justify {
+            self.write_repeated(b' ', padding)?;
+        }
+        Ok(())
+    }
+
+    fn next_float(
+        &mut self,
+        spec: &ConversionSpec,
+        kind: FloatKind,
+        upper: bool,
+    ) -> Result<(), Error<W::Error>> {
+        if spec.length == LengthModifier::LongDouble {
+            return Err(Error::InvalidFormat);
+        }
+        let value = self.arg(ArgKind::Double, spec.position).as_double();
+
+        let sign = spec.sign(value.is_sign_negative());
+        let value = value.abs();
+
+        if !value.is_finite() {
+            let body: &[u8] = match (value.is_nan(), upper) {
+                (true, false) => b"nan",
+                (true, true) => b"NAN",
+                (false, false) => b"inf",
+                (false, true) => b"INF",
+            };
+            return self.write_padded(spec, sign, 0, body);
+        }
+
+        let mut body = FloatBuf(Vec::new());
+        match kind {
+            FloatKind::Fixed => {
+                body.write_fixed(value, spec.precision.unwrap_or(6), spec.alternate)
+            }
+            FloatKind::Exponent => {
+                body.write_exponent(value, spec.precision.unwrap_or(6), spec.alternate, upper)
+            }
+            FloatKind::General => {
+                body.write_general(value, spec.precision.unwrap_or(6), spec.alternate, upper)
+            }
+            FloatKind::Hex => body.write_hex(value, spec.precision, spec.alternate, upper),
+        }
+
+        let mut prefix = [0u8; 3];
+        let mut prefix_len = sign.len();
+        prefix[..prefix_len].copy_from_slice(sign);
+        if kind == FloatKind::Hex {
+            prefix[prefix_len..prefix_len + 2].copy_from_slice(if upper { b"0X" } else { b"0x" });
+            prefix_len += 2;
+        }
+        let prefix = &prefix[..prefix_len];
+
+        let zeroes = spec.zero_fill(prefix.len() + body.0.len());
+        self.write_padded(spec, prefix, zeroes, &body.0)
+    }
+
+    /// Reads the next integer argument as specified by `length`, returns whether or not it is negative and its magnitude.
+    fn next_int_arg(&mut self, spec: &ConversionSpec, signed: bool) -> (bool, u64) {
+        let kind = match spec.length {
+            LengthModifier::None | LengthModifier::Char | LengthModifier::Short => ArgKind::Int,
+            _ => ArgKind::LongLong,
+        };
+        let arg = self.arg(kind, spec.position);
+
+        let raw: i64 = match spec.length {
+            LengthModifier::None if signed => arg.as_int() as i64,
+            LengthModifier::None => arg.as_int() as c_uint as i64,
+            LengthModifier::Char if signed => arg.as_int() as c_schar as i64,
+            LengthModifier::Char => arg.as_int() as c_uchar as i64,
+            LengthModifier::Short if signed => arg.as_int() as c_short as i64,
+            LengthModifier::Short => arg.as_int() as c_ushort as i64,
+            LengthModifier::Long
+            | LengthModifier::LongLong
+            | LengthModifier::LongDouble
+            | LengthModifier::SizeT
+            | LengthModifier::PtrDiffT
+            | LengthModifier::MaxT => arg.as_long_long(),
+        };
+
+        if signed {
+            (raw < 0, raw.unsigned_abs())
+        } else {
+            (false, raw as u64)
+        }
+    }
+
+    fn write_int(
+        &mut self,
+        spec: &ConversionSpec,
+        kind: Kind,
+        negative: bool,
+        value: u64,
+    ) -> Result<(), Error<W::Error>> {
+        let mut buf = [0u8; 64];
+        let mut start = buf.len();
+
+        let mut rest = value;
+        while rest != 0 {
+            start -= 1;
+            buf[start] = kind.digits()[(rest % kind.radix()) as usize];
+            rest /= kind.radix();
+        }
+
+        // A zero value with a zero precision prints no digits at all
+        if value == 0 && spec.precision != Some(0) {
+            start -= 1;
+            buf[start] = b'0';
+        }
+        let digits = &buf[start..];
+
+        let prefix: &[u8] = match kind {
+            Kind::Normal => spec.sign(negative),
+            Kind::Hex if spec.alternate && value != 0 => b"0x",
+            Kind::BigHex if spec.alternate && value != 0 => b"0X",
+            Kind::Binary if spec.alternate && value != 0 => b"0b",
+            Kind::BigBinary if spec.alternate && value != 0 => b"0B",
+            Kind::Binary | Kind::BigBinary | Kind::Octal | Kind::Hex | Kind::BigHex => b"",
+        };
+
+        let mut zeroes = match spec.precision {
+            Some(prec) => prec.saturating_sub(digits.len()),
+            None => spec.zero_fill(prefix.len() + digits.len()),
+        };
+
+        // The alternative form of octal forces the first digit to be a zero
+        if kind == Kind::Octal && spec.alternate && zeroes == 0 && digits.first() != Some(&b'0') {
+            zeroes = 1;
+        }
+
+        self.write_padded(spec, prefix, zeroes, digits)
+    }
+
+    fn next_int(
+        &mut self,
+        spec: &ConversionSpec,
+        signed: bool,
+        kind: Kind,
+    ) -> Result<(), Error<W::Error>> {
+        let (negative, value) = self.next_int_arg(spec, signed);
+        self.write_int(spec, kind, negative, value)
+    }
+
+    /// Writes a conversion registered with `safa_register_printf_specifier`, padded to the field width.
+    fn write_custom(
+        &mut self,
+        spec: &ConversionSpec,
+        conversion: u8,
+        custom: CustomSpecifier,
+    ) -> Result<(), Error<W::Error>> {
+        let info = spec.printf_info(conversion);
+        let kinds = custom.arg_kinds(&info).ok_or(Error::InvalidFormat)?;
+
+        // The handler must ask for the arguments that were read up front
+        if let Some(args) = &self.numbered_args
+            && let Some(position) = spec.position
+        {
+            let read = args.get(position - 1..).unwrap_or_default();
+            if kinds.len() > read.len()
+                || kinds
+                    .iter()
+                    .zip(read)
+                    .any(|(kind, arg)| arg.kind() != *kind)
+            {
+                return Err(Error::InvalidFormat);
+            }
+        }
+
+        let args: Vec<Arg> = kinds
+            .into_iter()
+            .enumerate()
+            .map(|(i, kind)| self.arg(kind, spec.position.map(|position| position + i)))
+            .collect();
+
+        let body = custom.format(&info, &args).ok_or(Error::CustomFailed)?;
+        self.write_padded(spec, b"", 0, &body)
+    }
+
+    fn write_next_fmt(&mut self, spec_start: usize) -> Result<(), Error<W::Error>> {
+        let parsed = self.cursor.make_spec();
+        let Some(conversion) = parsed.conversion else {
+            // A lone `%` at the end of the format is printed as is
+            let fmt = self.cursor.fmt;
+            self.write_bytes(&fmt[spec_start..])?;
+            return Ok(());
+        };
+
+        if conversion == b'%' {
+            self.write_byte(b'%')?;
+            return Ok(());
+        }
+
+        let spec = self.resolve_spec(&parsed);
+        if let Some(custom) = self.specifiers.find(conversion) {
+            return self.write_custom(&spec, conversion, custom);
+        }
+
+        match conversion {
+            b'c' => {
+                let c = self.arg(ArgKind::Int, spec.position).as_int() as c_uchar;
+                self.write_padded(&spec, b"", 0, &[c])
+            }
+            b'd' | b'i' => self.next_int(&spec, true, Kind::Normal),
+            b'f' => self.next_float(&spec, FloatKind::Fixed, false),
+            b'F' => self.next_float(&spec, FloatKind::Fixed, true),
+            b'e' => self.next_float(&spec, FloatKind::Exponent, false),
+            b'E' => self.next_float(&spec, FloatKind::Exponent, true),
+            b'g' => self.next_float(&spec, FloatKind::General, false),
+            b'G' => self.next_float(&spec, FloatKind::General, true),
+            b'a' => self.next_float(&spec, FloatKind::Hex, false),
+            b'A' => self.next_float(&spec, FloatKind::Hex, true),
+            b'u' => self.next_int(&spec, false, Kind::Normal),
+            b'b' => self.next_int(&spec, false, Kind::Binary),
+            b'B' => self.next_int(&spec, false, Kind::BigBinary),
+            b'o' => self.next_int(&spec, false, Kind::Octal),
+            b'x' => self.next_int(&spec, false, Kind::Hex),
+            b'X' => self.next_int(&spec, false, Kind::BigHex),
+            b'p' => {
+                let ptr = self.arg(ArgKind::Pointer, spec.position).as_ptr();
+                if ptr.is_null() {
+                    return self.write_padded(&spec, b"", 0, b"(nil)");
+                }
+
+                let spec = ConversionSpec {
+                    alternate: true,
+                    ..spec
+                };
+                self.write_int(&spec, Kind::Hex, false, ptr as usize as u64)
+            }
+            b's' => {
+                let ptr = self.arg(ArgKind::Pointer, spec.position).as_ptr() as *const c_char;
+                let bytes: &[u8] = if ptr.is_null() {
+                    // As glibc does, rather than a truncated `(null)`
+                    if spec.precision.is_some_and(|prec| prec < b"(null)".len()) {
+                        b""
+                    } else {
+                        b"(null)"
+                    }
+                } else {
+                    // SAFETY: the caller of `printf_to` guarantees that the argument is a string,
+                    // which doesn't have to be NUL terminated if it is at least as long as the precision
+                    let len = match spec.precision {
+                        Some(prec) => (0..prec)
+                            .position(|i| unsafe { *ptr.add(i) } == 0)
+                            .unwrap_or(prec),
+                        None => unsafe { CStr::from_ptr(ptr) }.count_bytes(),
+                    };
+                    unsafe { core::slice::from_raw_parts(ptr as *const u8, len) }
+                };
+                self.write_padded(&spec, b"", 0, bytes)
+            }
+            _ => {
+                // Unknown conversions are printed as is
+                let fmt = self.cursor.fmt;
+                self.write_bytes(&fmt[spec_start..self.cursor.curr_index])?;
+                Ok(())
+            }
+        }
+    }
+
+    fn write_all(mut self) -> Result<usize, Error<W::Error>> {
+        while let Some(byte) = self.cursor.next() {
+            if byte == b'%' {
+                self.write_next_fmt(self.cursor.curr_index - 1)?;
+            } else {
+                self.write_byte(byte)?;
+            }
+        }
+        Ok(self.wrote)
+    }
+}
+
+/// Writes `fmt` formatted with `args` to `writer` as the printf family does,
+/// `args` is usually a [`core::ffi::VaList`] but can be any [`ArgSource`].
+/// Conversions found in `specifiers` take precedence over the standard ones.
+///
+/// Returns the amount of bytes written.
+///
+/// # Safety
+/// `args` must hold an argument of the matching type for every conversion of `fmt`,
+/// see [`ArgSource::next_arg`], and every `%s` argument must either be null or point to a string
+/// that is NUL terminated or at least as long as the precision.
+pub unsafe fn printf_to<W: CWriter, A: ArgSource>(
+    writer: &mut W,
+    fmt: &[u8],
+    args: A,
+    specifiers: &dyn CustomSpecifiers,
+) -> Result<usize, Error<W::Error>> {
+    let printer = unsafe { CPrinter::new(writer, fmt, args, specifiers)? };
+    printer.write_all()
+}
@@ -0,0 +1,26 @@
+//! The printf and scanf engines of salibc.
+//!
+//! Kept apart from the libc itself so that they only depend on `core` and `alloc`,
+//! and can be unit tested on the host against what glibc does.
+#![no_std]
+#![feature(c_variadic)]
+
+extern crate alloc;
+
+pub mod args;
+pub mod custom;
+pub mod format;
+pub mod parse;
+
+/// Why a printf or scanf call failed, `E` is the error type of the writer or reader.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error<E> {
+    /// The writer or reader failed
+    Io(E),
+    /// The writer accepted none of the bytes it was given
+    WriteZero,
+    /// The format can't be followed, e.g. it mixes numbered and unnumbered arguments or converts a `long double`
+    InvalidFormat,
+    /// The handler of a custom conversion failed
+    CustomFailed,
+}
@@ -0,0 +1,725 @@
+use core::convert::Infallible;
+use core::ffi::{c_char, c_double, c_float, c_uchar, c_uint, c_ulonglong, c_ushort, c_void};
+
+use alloc::vec::Vec;
+
+use crate::{
+    Error,
+    args::{ArgKind, ArgSource},
+    format::{FmtCursor, LengthModifier},
+};
+
+#[derive(Debug)]
+pub struct BufReader<'a>(&'a [u8], usize);
+impl<'a> BufReader<'a> {
+    pub const fn new(b: &'a [u8]) -> Self {
+        Self(b, 0)
+    }
+}
+
+pub trait CReader {
+    type Error;
+    fn read_bytes(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error>;
+    fn read_byte(&mut self) -> Result<Option<u8>, Self::Error> {
+        let mut buf = [0u8];
+        let r = self.read_bytes(&mut buf)?;
+        if r == 0 { Ok(None) } else { Ok(Some(buf[0])) }
+    }
+    fn unread_byte(&mut self, b: u8);
+
+    fn read_bytes_until_or_eof_excluding<F: FnMut(u8) -> bool>(
+        &mut self,
+        buf: &mut [u8],
+        mut until: F,
+    ) -> Result<usize, Self::Error> {
+        let mut read = 0;
+
+        while read < buf.len() {
+            let c = self.read_byte()?;
+            match c {
+                Some(c) => {
+                    if until(c) {
+                        self.unread_byte(c);
+                        break;
+                    }
+
+                    buf[read] = c;
+                    read += 1;
+                }
+                None => break,
+            }
+        }
+
+        Ok(read)
+    }
+    fn read_bytes_until_or_eof<F: FnMut(u8) -> bool>(
+        &mut self,
+        buf: &mut [u8],
+        mut until: F,
+    ) -> Result<usize, Self::Error> {
+        let mut read = 0;
+
+        while read < buf.len() {
+            let c = self.read_byte()?;
+            match c {
+                Some(c) => {
+                    buf[read] = c;
+                    read += 1;
+                    if until(c) {
+                        break;
+                    }
+                }
+                None => break,
+            }
+        }
+
+        Ok(read)
+    }
+
+    fn read_bytes_until_or_eof_alloc<F: FnMut(u8) -> bool>(
+        &mut self,
+        buf: &mut Vec<u8>,
+        max: usize,
+        mut until: F,
+    ) -> Result<usize, Self::Error> {
+        let mut read = 0;
+
+        while read < max {
+            let c = self.read_byte()?;
+            match c {
+                Some(c) => {
+                    buf.push(c);
+                    read += 1;
+                    if until(c) {
+                        break;
+                    }
+                }
+                None => break,
+            }
+        }
+
+        Ok(read)
+    }
+}
+
+impl<'a> CReader for BufReader<'a> {
+    type Error = Infallible;
+    fn unread_byte(&mut self, _b: u8) {
+        self.1 -= 1;
+    }
+    fn read_bytes(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        if self.0.len() <= self.1 {
+            return Ok(0);
+        }
+
+        let to = &self.0[self.1..];
+        let amount = to.len().min(buf.len());
+        buf[..amount].copy_from_slice(&to[..amount]);
+        self.1 += amount;
+
+        Ok(amount)
+    }
+}
+
+struct CReaderWrapper<'a, T: CReader>(&'a mut T, usize);
+
+impl<'a, T: CReader> CReader for CReaderWrapper<'a, T> {
+    type Error = T::Error;
+    fn read_bytes(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        let am = self.0.read_bytes(buf)?;
+        self.1 += am;
+        Ok(am)
+    }
+    fn unread_byte(&mut self, b: u8) {
+        self.0.unread_byte(b);
+        self.1 -= 1;
+    }
+}
+
+/// Returns whether `c` is a white-space character as `isspace` in the C locale.
+const fn is_space(c: u8) -> bool {
+    matches!(c, b' ' | b'\t' | b'\n' | b'\x0b' | b'\x0c' | b'\r')
+}
+
+/// Returns 2 to the power of `exp`, which must be in the normal range.
+const fn exp2(exp: i64) -> f64 {
+    f64::from_bits(((exp + 1023) as u64) << 52)
+}
+
+/// Stores `value` through `ptr` as the integer type selected by `length`, truncating it.
+unsafe fn store_int(ptr: *mut c_void, value: u64, length: LengthModifier) {
+    unsafe {
+        match length {
+            LengthModifier::Char => ptr.cast::<c_uchar>().write(value as c_uchar),
+            LengthModifier::Short => ptr.cast::<c_ushort>().write(value as c_ushort),
+            LengthModifier::None => ptr.cast::<c_uint>().write(value as c_uint),
+            _ => ptr.cast::<c_ulonglong>().write(value as c_ulonglong),
+        }
+    }
+}
+
+/// Returns the bits of `value` as a `long double`, which is the x87 80-bit extended precision format on x86_64
+/// and the IEEE 754 binary128 format on aarch64.
+const fn long_double_bits(value: f64) -> u128 {
+    let bits = value.to_bits();
+    let sign = (bits >> 63) as u128;
+    let exponent = ((bits >> 52) & 0x7ff) as i64;
+    let fraction = bits & ((1 << 52) - 1);
+
+    // The biased `long double` exponent and the mantissa with its leading 1 at bit 52
+    let (exponent, mantissa) = match exponent {
+        0 if fraction == 0 => (0, 0),
+        // Subnormal doubles are normal `long double`s
+        0 => {
+            let shift = fraction.leading_zeros() as i64 - 11;
+            (16383 - 1022 - shift, fraction << shift)
+        }
+        // Infinities and NaNs
+        0x7ff => (0x7fff, fraction | 1 << 52),
+        _ => (exponent - 1023 + 16383, fraction | 1 << 52),
+    };
+    let exponent = exponent as u128;
+
+    if cfg!(target_arch = "x86_64") {
+        // The leading 1 is explicit
+        sign << 79 | exponent << 64 | (mantissa as u128) << 11
+    } else {
+        sign << 127 | exponent << 112 | ((mantissa & !(1 << 52)) as u128) << 60
+    }
+}
+
+/// Stores `value` through `ptr` as the floating point type selected by `length`,
+/// a `long double` only keeps the precision of `value`.
+unsafe fn store_float(ptr: *mut c_void, value: f64, length: LengthModifier) {
+    unsafe {
+        match length {
+            LengthModifier::Long => ptr.cast::<c_double>().write(value),
+            LengthModifier::LongDouble => ptr.cast::<u128>().write(long_double_bits(value)),
+            _ => ptr.cast::<c_float>().write(value as c_float),
+        }
+    }
+}
+
+/// Stores the `index`th character of a string conversion, as a 32-bit `wchar_t` if `wide` is set.
+unsafe fn store_char(ptr: *mut c_void, index: usize, c: u8, wide: bool) {
+    unsafe {
+        if wide {
+            ptr.cast::<u32>().add(index).write(c as u32);
+        } else {
+            ptr.cast::<c_char>().add(index).write(c as c_char);
+        }
+    }
+}
+
+/// The set of bytes matched by a `%[...]` conversion.
+struct ScanSet([bool; 256]);
+
+impl ScanSet {
+    /// Parses a scanset, the cursor must be right after the `[`, returns `None` if it isn't terminated.
+    fn parse(cursor: &mut FmtCursor) -> Option<Self> {
+        let negated = cursor.peek() == Some(b'^');
+        if negated {
+            cursor.next();
+        }
+
+        let mut set = [false; 256];
+        // A `]` right after the `[` or `[^` is part of the set
+        let mut first = true;
+        loop {
+            let c = cursor.next()?;
+            if c == b']' && !first {
+                break;
+            }
+            first = false;
+
+            if cursor.peek() == Some(b'-') {
+                cursor.next();
+                match cursor.next()? {
+                    // A `-` at the end of the set is taken literally
+                    b']' => {
+                        set[c as usize] = true;
+                        set[b'-' as usize] = true;
+                        break;
+                    }
+                    end => {
+                        for b in c..=end {
+                            set[b as usize] = true;
+                        }
+                    }
+                }
+            } else {
+                set[c as usize] = true;
+            }
+        }
+
+        if negated {
+            set.iter_mut().for_each(|b| *b = !*b);
+        }
+        Some(Self(set))
+    }
+
+    const fn contains(&self, c: u8) -> bool {
+        self.0[c as usize]
+    }
+}
+
+/// Scans a whole format string, only created by [`scanf_from`] whose safety requirements it relies on.
+struct CScanner<'a, 'fmt, R: CReader, A: ArgSource> {
+    reader: CReaderWrapper<'a, R>,
+    cursor: FmtCursor<'fmt>,
+    args: A,
+    /// The amount of conversions assigned so far
+    matched: usize,
+    /// The `*` flag of the current conversion, the conversion is performed but not stored
+    suppress: bool,
+    /// The amount of bytes the current conversion may still read
+    limit: usize,
+    /// Whether or not the end of input was reached
+    eof: bool,
+    /// The amount of bytes read when the current directive started to read its input
+    directive_start: usize,
+}
+
+impl<'a, 'fmt, R: CReader, A: ArgSource> CScanner<'a, 'fmt, R, A> {
+    /// Takes the next argument as a pointer to the object to store a conversion in.
+    fn next_ptr<T>(&mut self) -> *mut T {
+        // SAFETY: guaranteed by the caller of `scanf_from`
+        unsafe { self.args.next_arg(ArgKind::Pointer) }
+            .as_ptr()
+            .cast_mut()
+            .cast()
+    }
+
+    /// Reads the next byte if `accept` returns true for it, leaves it unread otherwise.
+    ///
+    /// Reads nothing once the field width of the current conversion is exhausted.
+    fn next_byte_if<F: FnOnce(u8) -> bool>(&mut self, accept: F) -> Result<Option<u8>, R::Error> {
+        if self.limit == 0 {
+            return Ok(None);
+        }
+
+        match self.reader.read_byte()? {
+            Some(c) if accept(c) => {
+                self.limit -= 1;
+                Ok(Some(c))
+            }
+            Some(c) => {
+                self.reader.unread_byte(c);
+                Ok(None)
+            }
+            None => {
+                self.eof = true;
+                Ok(None)
+            }
+        }
+    }
+
+    /// Skips any amount of white-space.
+    fn skip_space(&mut self) -> Result<(), R::Error> {
+        while self.next_byte_if(is_space)?.is_some() {}
+        Ok(())
+    }
+
+    /// Reads as much of `word` as the input matches ignoring case, returns the amount of bytes matched.
+    fn scan_word(&mut self, word: &[u8]) -> Result<usize, R::Error> {
+        for (i, expected) in word.iter().enumerate() {
+            if self
+                .next_byte_if(|c| c.eq_ignore_ascii_case(expected))?
+                .is_none()
+            {
+                return Ok(i);
+            }
+        }
+        Ok(word.len())
+    }
+
+    /// Reads an integer as `strtoull` does in base `radix`, 0 detecting the base from the prefix,
+    /// returns `None` if there is none.
+    fn scan_int(&mut self, mut radix: u32) -> Result<Option<u64>, R::Error> {
+        let negative = self.next_byte_if(|c| c == b'+' || c == b'-')? == Some(b'-');
+
+        let mut any_digits = false;
+        if matches!(radix, 0 | 2 | 16) && self.next_byte_if(|c| c == b'0')?.is_some() {
+            any_digits = true;
+            let prefix = self.next_byte_if(|c| match radix {
+                0 => matches!(c, b'x' | b'X' | b'b' | b'B'),
+                2 => matches!(c, b'b' | b'B'),
+                _ => matches!(c, b'x' | b'X'),
+            })?;
+
+            radix = match prefix {
+                Some(b'x' | b'X') => 16,
+                Some(b'b' | b'B') => 2,
+                _ if radix == 0 => 8,
+                _ => radix,
+            };
+        } else if radix == 0 {
+            radix = 10;
+        }
+
+        let mut value: u64 = 0;
+        while let Some(c) = self.next_byte_if(|c| (c as char).is_digit(radix))? {
+            any_digits = true;
+            let digit = (c as char).to_digit(radix).unwrap_or_default();
+            value = value
+                .saturating_mul(radix as u64)
+                .saturating_add(digit as u64);
+        }
+
+        if !any_digits {
+            return Ok(None);
+        }
+        Ok(Some(if negative {
+            value.wrapping_neg()
+        } else {
+            value
+        }))
+    }
+
+    /// Reads a floating point number as `strtod` does, returns `None` if there is none.
+    fn scan_float(&mut self) -> Result<Option<f64>, R::Error> {
+        let negative = self.next_byte_if(|c| c == b'+' || c == b'-')? == Some(b'-');
+
+        let value = match self.next_byte_if(|c| matches!(c, b'i' | b'I' | b'n' | b'N' | b'0'))? {
+            Some(b'i' | b'I') => {
+                if self.scan_word(b"nf")? != 2 {
+                    return Ok(None);
+                }
+                // A partial `infinity` can't be pushed back, glibc fails on it as well
+                if !matches!(self.scan_word(b"inity")?, 0 | 5) {
+                    return Ok(None);
+                }
+                Some(f64::INFINITY)
+            }
+            Some(b'n' | b'N') => {
+                if self.scan_word(b"an")? != 2 {
+                    return Ok(None);
+                }
+                if self.next_byte_if(|c| c == b'(')?.is_some() {
+                    while self
+                        .next_byte_if(|c| c.is_ascii_alphanumeric() || c == b'_')?
+                        .is_some()
+                    {}
+                    if self.next_byte_if(|c| c == b')')?.is_none() {
+                        return Ok(None);
+                    }
+                }
+                Some(f64::NAN)
+            }
+            Some(_) => {
+                if self.next_byte_if(|c| c == b'x' || c == b'X')?.is_some() {
+                    self.scan_hex_float()?
+                } else {
+                    self.scan_decimal_float(b"0")?
+                }
+            }
+            None => self.scan_decimal_float(b"")?,
+        };
+
+        Ok(value.map(|value| if negative { -value } else { value }))
+    }
+
+    /// Reads the digits, fraction and exponent of a decimal floating point number,
+    /// `prefix` are the digits that were already read.
+    fn scan_decimal_float(&mut self, prefix: &[u8]) -> Result<Option<f64>, R::Error> {
+        let mut text = Vec::from(prefix);
+        let mut any_digits = !prefix.is_empty();
+
+        while let Some(c) = self.next_byte_if(|c| c.is_ascii_digit())? {
+            text.push(c);
+            any_digits = true;
+        }
+
+        if self.next_byte_if(|c| c == b'.')?.is_some() {
+            text.push(b'.');
+            while let Some(c) = self.next_byte_if(|c| c.is_ascii_digit())? {
+                text.push(c);
+                any_digits = true;
+            }
+        }
+
+        if !any_digits {
+            return Ok(None);
+        }
+
+        if self.next_byte_if(|c| c == b'e' || c == b'E')?.is_some() {
+            let mantissa_len = text.len();
+            text.push(b'e');
+            if let Some(sign) = self.next_byte_if(|c| c == b'+' || c == b'-')? {
+                text.push(sign);
+            }
+
+            let mut any_digits = false;
+            while let Some(c) = self.next_byte_if(|c| c.is_ascii_digit())? {
+                text.push(c);
+                any_digits = true;
+            }
+            // An exponent without digits is consumed but ignored, as glibc does
+            if !any_digits {
+                text.truncate(mantissa_len);
+            }
+        }
+
+        Ok(str::from_utf8(&text)
+            .ok()
+            .and_then(|text| text.parse().ok()))
+    }
+
+    /// Reads the digits and binary exponent of a hexadecimal floating point number, the `0x` must already be read.
+    fn scan_hex_float(&mut self) -> Result<Option<f64>, R::Error> {
+        let mut mantissa: u64 = 0;
+        let mut exponent: i64 = 0;
+        // Whether or not any of the digits that didn't fit in `mantissa` is non zero
+        let mut sticky = false;
+        let mut dotted = false;
+
+        loop {
+            if !dotted && self.next_byte_if(|c| c == b'.')?.is_some() {
+                dotted = true;
+                continue;
+            }
+
+            let Some(c) = self.next_byte_if(|c| c.is_ascii_hexdigit())? else {
+                break;
+            };
+            let digit = (c as char).to_digit(16).unwrap_or_default() as u64;
+            if mantissa >> 60 == 0 {
+                mantissa = mantissa << 4 | digit;
+                if dotted {
+                    exponent -= 4;
+                }
+            } else {
+                sticky |= digit != 0;
+                if !dotted {
+                    exponent += 4;
+                }
+            }
+        }
+
+        if self.next_byte_if(|c| c == b'p' || c == b'P')?.is_some() {
+            let negative = self.next_byte_if(|c| c == b'+' || c == b'-')? == Some(b'-');
+
+            let mut any_digits = false;
+            let mut exp: i64 = 0;
+            while let Some(c) = self.next_byte_if(|c| c.is_ascii_digit())? {
+                any_digits = true;
+                exp = exp.saturating_mul(10).saturating_add((c - b'0') as i64);
+            }
+            if any_digits {
+                exponent = exponent.saturating_add(if negative { -exp } else { exp });
+            }
+        }
+
+        // The sticky bit is below the rounding point as the mantissa has more than 53 bits once it is set
+        let mut value = (mantissa | sticky as u64) as f64;
+        // Anything past these limits overflows or underflows anyway
+        let mut exponent = exponent.clamp(-1200, 1200);
+        while exponent != 0 && value != 0. {
+            let step = exponent.clamp(-1000, 1000);
+            value *= exp2(step);
+            exponent -= step;
+        }
+        Ok(Some(value))
+    }
+
+    /// Reads an integer and stores it as specified by `length`.
+    fn scan_int_conversion(
+        &mut self,
+        radix: u32,
+        length: LengthModifier,
+    ) -> Result<bool, R::Error> {
+        let Some(value) = self.scan_int(radix)? else {
+            return Ok(false);
+        };
+
+        self.assign(|ptr| unsafe { store_int(ptr, value, length) });
+        Ok(true)
+    }
+
+    /// Takes the next argument and stores a conversion through it with `store`, unless assignment is suppressed.
+    fn assign<T, F: FnOnce(*mut T)>(&mut self, store: F) {
+        if !self.suppress {
+            store(self.next_ptr());
+            self.matched += 1;
+        }
+    }
+
+    /// Reads bytes as long as `accept` returns true for them, storing them as `wchar_t`s if `wide` is set,
+    /// fails if no bytes were read.
+    fn scan_chars<F: FnMut(u8) -> bool>(
+        &mut self,
+        mut accept: F,
+        wide: bool,
+        nul_terminate: bool,
+    ) -> Result<bool, R::Error> {
+        let ptr: *mut c_void = if self.suppress {
+            core::ptr::null_mut()
+        } else {
+            self.next_ptr()
+        };
+
+        let mut len = 0;
+        while let Some(c) = self.next_byte_if(&mut accept)? {
+            if !ptr.is_null() {
+                unsafe { store_char(ptr, len, c, wide) };
+            }
+            len += 1;
+        }
+
+        if len == 0 {
+            return Ok(false);
+        }
+
+        if !ptr.is_null() {
+            if nul_terminate {
+                unsafe { store_char(ptr, len, b'\0', wide) };
+            }
+            self.matched += 1;
+        }
+        Ok(true)
+    }
+
+    /// Performs the conversion specification right after a `%`, returns whether or not scanning should go on.
+    fn scan_conversion(&mut self) -> Result<bool, R::Error> {
+        self.suppress = self.cursor.peek() == Some(b'*');
+        if self.suppress {
+            self.cursor.next();
+        }
+
+        let width = self.cursor.try_make_number().filter(|width| *width != 0);
+        let length = self
+            .cursor
+            .try_make_length()
+            .unwrap_or(LengthModifier::None);
+        let Some(conversion) = self.cursor.next() else {
+            return Ok(false);
+        };
+
+        // Every conversion but these skips leading white-space
+        if !matches!(conversion, b'c' | b'[' | b'n') {
+            self.skip_space()?;
+        }
+        self.directive_start = self.reader.1;
+
+        let wide = length == LengthModifier::Long;
+        // `%c` reads a single character unless a width is given
+        self.limit = width.unwrap_or(if conversion == b'c' { 1 } else { usize::MAX });
+
+        let result = match conversion {
+            b'%' => Ok(self.next_byte_if(|c| c == b'%')?.is_some()),
+            b'd' | b'u' => self.scan_int_conversion(10, length),
+            b'i' => self.scan_int_conversion(0, length),
+            b'o' => self.scan_int_conversion(8, length),
+            b'x' | b'X' => self.scan_int_conversion(16, length),
+            b'b' => self.scan_int_conversion(2, length),
+            b'a' | b'A' | b'e' | b'E' | b'f' | b'F' | b'g' | b'G' => {
+                let Some(value) = self.scan_float()? else {
+                    return Ok(false);
+                };
+
+                self.assign(|ptr| unsafe { store_float(ptr, value, length) });
+                Ok(true)
+            }
+            b'p' => {
+                let Some(value) = self.scan_int(16)? else {
+                    return Ok(false);
+                };
+
+                self.assign(|ptr: *mut *mut c_void| unsafe {
+                    ptr.write(value as usize as *mut c_void)
+                });
+                Ok(true)
+            }
+            b'c' => self.scan_chars(|_| true, wide, false),
+            b's' => self.scan_chars(|c| !is_space(c), wide, true),
+            b'[' => {
+                let Some(set) = ScanSet::parse(&mut self.cursor) else {
+                    return Ok(false);
+                };
+                self.scan_chars(|c| set.contains(c), wide, true)
+            }
+            b'n' => {
+                // Doesn't count as a conversion
+                if !self.suppress {
+                    let consumed = self.reader.1 as u64;
+                    unsafe { store_int(self.next_ptr(), consumed, length) };
+                }
+                Ok(true)
+            }
+            _ => Ok(false),
+        };
+
+        self.limit = usize::MAX;
+        result
+    }
+
+    /// Scans the whole format, returns whether or not it stopped on an input failure,
+    /// that is the end of input was reached before the failing directive read anything.
+    fn scan_all(&mut self) -> Result<bool, R::Error> {
+        while let Some(byte) = self.cursor.next() {
+            let go_on = if byte == b'%' {
+                self.scan_conversion()?
+            } else if is_space(byte) {
+                // A white-space directive matches any amount of white-space, including none
+                self.skip_space()?;
+                true
+            } else {
+                self.directive_start = self.reader.1;
+                self.next_byte_if(|c| c == byte)?.is_some()
+            };
+
+            if !go_on {
+                return Ok(self.eof && self.reader.1 == self.directive_start);
+            }
+        }
+        Ok(false)
+    }
+}
+
+/// A failure of [`scanf_from`], along with the amount of conversions assigned before it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScanfError<E> {
+    pub error: Error<E>,
+    pub assigned: usize,
+}
+
+/// Reads from `reader` as specified by `fmt` as the scanf family does, storing the results through the pointers taken from `args`,
+/// `args` is usually a [`core::ffi::VaList`] but can be any [`ArgSource`].
+///
+/// Returns the amount of bytes read and the amount of conversions assigned,
+/// which is `None` if the input ended before anything was assigned.
+/// Fails if the reader does, conversions assigned before the failure are kept.
+///
+/// # Safety
+/// `args` must hold a pointer for every conversion of `fmt` that assigns, see [`ArgSource::next_arg`],
+/// pointing to a writable object of the type selected by the conversion and its length modifier,
+/// large enough for the whole field for `%c`, `%s` and `%[`.
+pub unsafe fn scanf_from<R: CReader, A: ArgSource>(
+    reader: &mut R,
+    fmt: &[u8],
+    args: A,
+) -> Result<(usize, Option<usize>), ScanfError<R::Error>> {
+    let mut scanner = CScanner {
+        reader: CReaderWrapper(reader, 0),
+        cursor: FmtCursor::new(fmt),
+        args,
+        matched: 0,
+        suppress: false,
+        limit: usize::MAX,
+        eof: false,
+        directive_start: 0,
+    };
+
+    // Scanning stops either at the end of the format, on a matching failure or on an input failure,
+    // which is only reported if nothing was assigned before it
+    let input_failure = scanner.scan_all().map_err(|e| ScanfError {
+        error: Error::Io(e),
+        assigned: scanner.matched,
+    })?;
+    let assigned = if input_failure && scanner.matched == 0 {
+        None
+    } else {
+        Some(scanner.matched)
+    };
+    Ok((scanner.reader.1, assigned))
+}
@@ -0,0 +1,604 @@
+//! printf conversions, the expected outputs of the tables are what glibc 2.36 prints
+//! for the same format and arguments.
+#![feature(c_variadic)]
+
+use core::ffi::{CStr, c_char, c_int, c_void};
+use core::ptr;
+
+use salibc_fmt::Error;
+use salibc_fmt::args::Arg;
+use salibc_fmt::custom::{CustomSpecifier, PA_DOUBLE, PA_FLAG_LONG_DOUBLE, PA_INT, SafaPrintfInfo};
+use salibc_fmt::format::{BufWriter, CWriter, NL_ARGMAX, printf_to};
+
+/// Formats `fmt` into `out` through a [`core::ffi::VaList`] as `vsprintf` does, returns the length of the output.
+unsafe extern "C" fn format(out: *mut Vec<u8>, fmt: *const c_char, mut args: ...) -> c_int {
+    let fmt = unsafe { CStr::from_ptr(fmt) };
+    let len = unsafe { printf_to(&mut *out, fmt.to_bytes(), args.as_va_list(), &()) };
+    len.expect("formatting into a Vec shouldn't fail") as c_int
+}
+
+/// Checks that each format given its arguments prints the expected output.
+macro_rules! check {
+    ($($fmt:literal $(, $arg:expr)* => $expected:literal;)*) => {$(
+        let mut out = Vec::new();
+        let len = unsafe { format(&mut out, concat!($fmt, "\0").as_ptr().cast(), $($arg),*) };
+        assert_eq!(String::from_utf8_lossy(&out), $expected, "format {:?}", $fmt);
+        assert_eq!(len as usize, $expected.len(), "format {:?}", $fmt);
+    )*};
+}
+
+#[test]
+fn integers() {
+    check! {
+        "%d", 0i32 => "0";
+        "%d", 42i32 => "42";
+        "%d", -42i32 => "-42";
+        "%i", 2147483647i32 => "2147483647";
+        "%d", -2147483648i32 => "-2147483648";
+        "%u", 4294967295u32 => "4294967295";
+        "%u", -1i32 => "4294967295";
+        "%o", 8i32 => "10";
+        "%#o", 8i32 => "010";
+        "%#o", 0i32 => "0";
+        "%x", 255i32 => "ff";
+        "%X", 255i32 => "FF";
+        "%#x", 255i32 => "0xff";
+        "%#X", 255i32 => "0XFF";
+        "%#x", 0i32 => "0";
+    }
+}
+
+#[test]
+fn binary_integers() {
+    check! {
+        "%b", 5i32 => "101";
+        "%B", 6i32 => "110";
+        "%b", 0i32 => "0";
+        "%#b", 5i32 => "0b101";
+        "%#B", 5i32 => "0B101";
+        "%#b", 0i32 => "0";
+        "%#.0b|", 0i32 => "|";
+        "%08b", 5i32 => "00000101";
+        "%#010b", 5i32 => "0b00000101";
+        "%-6b|", 5i32 => "101   |";
+        "%.4b", 1i32 => "0001";
+        "%hhb", 257i32 => "1";
+        "%lb", -1i64 => "1111111111111111111111111111111111111111111111111111111111111111";
+    }
+}
+
+/// glibc 2.36 predates the C23 `wN` and `wfN` lengths, these are checked against the conversions
+/// `include/inttypes.h` uses for the same types instead.
+#[test]
+fn exact_and_fast_widths() {
+    check! {
+        "%w8d", 300i32 => "44";
+        "%w8u", -1i32 => "255";
+        "%w16d", 70000i32 => "4464";
+        "%w32d", -70000i32 => "-70000";
+        "%w64d", i64::MIN => "-9223372036854775808";
+        "%wf8u", 511i32 => "255";
+        "%wf16d", 70000i32 => "70000";
+        "%wf32x", 255i32 => "ff";
+        "%wf64x", u64::MAX => "ffffffffffffffff";
+        "%w64b", 1i64 << 40 => "10000000000000000000000000000000000000000";
+        "%w7d|", 1i32 => "%w7d|";
+    }
+}
+
+#[test]
+fn integer_flags() {
+    check! {
+        "%5d|", 42i32 => "   42|";
+        "%-5d|", 42i32 => "42   |";
+        "%05d", 42i32 => "00042";
+        "%05d", -42i32 => "-0042";
+        "%+d", 42i32 => "+42";
+        "%+d", -42i32 => "-42";
+        "% d", 42i32 => " 42";
+        "% +d", 42i32 => "+42";
+        "%+05d", 42i32 => "+0042";
+        "%-05d|", 42i32 => "42   |";
+        "%.3d", 7i32 => "007";
+        "%.0d|", 0i32 => "|";
+        "%5.0d|", 0i32 => "     |";
+        "%8.3d|", -7i32 => "    -007|";
+        "%08.3d|", 7i32 => "     007|";
+        "%#.3o", 8i32 => "010";
+        "%#5x|", 0i32 => "    0|";
+        "%#08x", 255i32 => "0x0000ff";
+        "%.0x|", 0i32 => "|";
+        "%#.0o", 0i32 => "0";
+        "%-#10x|", 3054i32 => "0xbee     |";
+    }
+}
+
+#[test]
+fn length_modifiers() {
+    check! {
+        "%hhd", 300i32 => "44";
+        "%hhu", -1i32 => "255";
+        "%hd", 70000i32 => "4464";
+        "%hu", -1i32 => "65535";
+        "%hhx", 511i32 => "ff";
+        "%ld", -9223372036854775808i64 => "-9223372036854775808";
+        "%lu", 18446744073709551615u64 => "18446744073709551615";
+        "%lld", 1234567890123i64 => "1234567890123";
+        "%llx", -1i64 => "ffffffffffffffff";
+        "%zu", 18446744073709551615u64 => "18446744073709551615";
+        "%zd", -1i64 => "-1";
+        "%td", -5i64 => "-5";
+        "%jd", -7i64 => "-7";
+        "%jx", 3735928559u64 => "deadbeef";
+    }
+}
+
+#[test]
+fn star_width_and_precision() {
+    check! {
+        "%*d|", 5i32, 42i32 => "   42|";
+        "%-*d|", 5i32, 42i32 => "42   |";
+        "%*d|", -5i32, 42i32 => "42   |";
+        "%.*d", 4i32, 7i32 => "0007";
+        "%.*d", -1i32, 7i32 => "7";
+        "%*.*f|", 8i32, 2i32, 1.23456 => "    1.23|";
+        "%.*s|", 2i32, c"hello".as_ptr() => "he|";
+    }
+}
+
+#[test]
+fn numbered_arguments() {
+    check! {
+        "%2$d %1$d", 1i32, 2i32 => "2 1";
+        "%1$d %1$d", 7i32 => "7 7";
+        "%2$s %1$s", c"a".as_ptr(), c"b".as_ptr() => "b a";
+        "%1$*2$d|", 42i32, 6i32 => "    42|";
+        "%1$.*2$f", 2.5, 3i32 => "2.500";
+        "%3$d %1$d", 1i32, 2i32, 3i32 => "3 1";
+        "%2$lld %1$f", 0.5, -9i64 => "-9 0.500000";
+        "%1$*2$.*3$f|", 1.23456, 10i32, 2i32 => "      1.23|";
+        "%1$s %1$.2s", c"hello".as_ptr() => "hello he";
+        "%2$c%1$c%2$c", b'a' as c_int, b'b' as c_int => "bab";
+        "%3$s %2$x %1$d", 10i32, 255i32, c"z".as_ptr() => "z ff 10";
+        "%1$*2$d|", 5i32, -4i32 => "5   |";
+        "%1$.*2$d", 5i32, -3i32 => "5";
+        "%2$p %1$lld", 1i64 << 40, 0x10usize as *const c_void => "0x10 1099511627776";
+    }
+}
+
+#[test]
+fn invalid_numbered_arguments() {
+    let args = [Arg::Int(1), Arg::Int(2)];
+    let past_nl_argmax = format!("%1$d %{}$d", NL_ARGMAX + 1);
+    let formats: [&[u8]; 4] = [b"%1$d %d", b"%d %2$d", b"%1$*d", past_nl_argmax.as_bytes()];
+    for fmt in formats {
+        let mut out = Vec::new();
+        let result = unsafe { printf_to(&mut out, fmt, args.iter(), &()) };
+        assert_eq!(
+            result,
+            Err(Error::InvalidFormat),
+            "format {:?}",
+            String::from_utf8_lossy(fmt)
+        );
+    }
+}
+
+#[test]
+fn chars_and_strings() {
+    check! {
+        "%c", 65i32 => "A";
+        "%3c|", 65i32 => "  A|";
+        "%-3c|", 65i32 => "A  |";
+        "%s", c"hello".as_ptr() => "hello";
+        "%10s|", c"hello".as_ptr() => "     hello|";
+        "%-10s|", c"hello".as_ptr() => "hello     |";
+        "%.3s", c"hello".as_ptr() => "hel";
+        "%.10s", c"hi".as_ptr() => "hi";
+        "%.0s|", c"x".as_ptr() => "|";
+        "%s", ptr::null::<c_char>() => "(null)";
+        "%8s|", ptr::null::<c_char>() => "  (null)|";
+        "%.3s|", ptr::null::<c_char>() => "|";
+        "%.6s|", ptr::null::<c_char>() => "(null)|";
+        "%-10.8s|", ptr::null::<c_char>() => "(null)    |";
+        "%%" => "%";
+        "100%%" => "100%";
+        "%5%|" => "%|";
+        "plain text" => "plain text";
+        "%y|" => "%y|";
+    }
+}
+
+#[test]
+fn pointers() {
+    check! {
+        "%p", 0x1234usize as *const c_void => "0x1234";
+        "%p", ptr::null::<c_void>() => "(nil)";
+        "%20p|", 0xdeadbeefusize as *const c_void => "          0xdeadbeef|";
+        "%-20p|", 0xdeadbeefusize as *const c_void => "0xdeadbeef          |";
+        "%10p|", ptr::null::<c_void>() => "     (nil)|";
+    }
+}
+
+#[test]
+fn fixed_floats() {
+    check! {
+        "%f", 1.23456 => "1.234560";
+        "%f", 0.0 => "0.000000";
+        "%f", -0.0 => "-0.000000";
+        "%.0f", 0.5 => "0";
+        "%.0f", 1.5 => "2";
+        "%.0f", 2.5 => "2";
+        "%.2f", 1.005 => "1.00";
+        "%.1f", 0.25 => "0.2";
+        "%#.0f", 3.0 => "3.";
+        "%10.3f|", -1.23456 => "    -1.235|";
+        "%-10.3f|", 1.23456 => "1.235     |";
+        "%010.3f", -1.23456 => "-00001.235";
+        "%+.2f", 2.0 => "+2.00";
+        "% .2f", 2.0 => " 2.00";
+        "%f", 1e20 => "100000000000000000000.000000";
+        "%f", 1e-07 => "0.000000";
+        "%.20f", 0.1 => "0.10000000000000000555";
+        "%f", 1.7976931348623157e308 => "179769313486231570814527423731704356798070567525844996598917476803157260780028538760589558632766878171540458953514382464234321326889464182768467546703537516986049910576551282076245490090389328944075868508455133942304583236903222948165808559332123348274797826204144723168738177180919299881250404026184124858368.000000";
+        "%.3f", 5e-324 => "0.000";
+        "%F", 1.5 => "1.500000";
+    }
+}
+
+#[test]
+fn exponent_floats() {
+    check! {
+        "%e", 1.23456 => "1.234560e+00";
+        "%e", 0.0 => "0.000000e+00";
+        "%E", 123456.789 => "1.234568E+05";
+        "%.0e", 12345.0 => "1e+04";
+        "%#.0e", 12345.0 => "1.e+04";
+        "%e", 1e-300 => "1.000000e-300";
+        "%e", 1e100 => "1.000000e+100";
+        "%.3e", 9.9995 => "9.999e+00";
+        "%12.4e|", -0.000123 => " -1.2300e-04|";
+        "%e", 5e-324 => "4.940656e-324";
+        "%+e", 1.0 => "+1.000000e+00";
+        "%.2e", 9.999 => "1.00e+01";
+    }
+}
+
+#[test]
+fn general_floats() {
+    check! {
+        "%g", 100000.0 => "100000";
+        "%g", 1000000.0 => "1e+06";
+        "%g", 0.0001 => "0.0001";
+        "%g", 1e-05 => "1e-05";
+        "%g", 1.23456 => "1.23456";
+        "%G", 1e-10 => "1E-10";
+        "%.0g", 123.0 => "1e+02";
+        "%.1g", 0.0 => "0";
+        "%#g", 1.0 => "1.00000";
+        "%#.3g", 100.0 => "100.";
+        "%g", 123456789.0 => "1.23457e+08";
+        "%.10g", 0.1 => "0.1";
+        "%g", -0.0 => "-0";
+        "%g", 9.9999995 => "10";
+        "%.3g", 9.995 => "9.99";
+        "%g", 1000000000000000.0 => "1e+15";
+        "%010g", -1.5 => "-0000001.5";
+        "%g", 999999.5 => "1e+06";
+        "%.2g", 9.9e-05 => "9.9e-05";
+    }
+}
+
+#[test]
+fn hex_floats() {
+    check! {
+        "%a", 1.0 => "0x1p+0";
+        "%a", 0.0 => "0x0p+0";
+        "%a", -2.5 => "-0x1.4p+1";
+        "%A", 255.0 => "0X1.FEP+7";
+        "%.2a", 1.0 => "0x1.00p+0";
+        "%.1a", 1.96875 => "0x2.0p+0";
+        "%.0a", 1.5 => "0x2p+0";
+        "%a", 5e-324 => "0x0.0000000000001p-1022";
+        "%a", 1.7976931348623157e308 => "0x1.fffffffffffffp+1023";
+        "%#.0a", 1.0 => "0x1.p+0";
+        "%.15a", 1.0 => "0x1.000000000000000p+0";
+        "%12a|", 1.0 => "      0x1p+0|";
+        "%012a", 1.0 => "0x0000001p+0";
+        "%a", 0.1 => "0x1.999999999999ap-4";
+    }
+}
+
+#[test]
+fn infinities_and_nans() {
+    check! {
+        "%f", f64::INFINITY => "inf";
+        "%F", f64::INFINITY => "INF";
+        "%e", f64::NEG_INFINITY => "-inf";
+        "%g", f64::NAN => "nan";
+        "%G", f64::NAN => "NAN";
+        "%5f|", f64::INFINITY => "  inf|";
+        "%-6f|", f64::NEG_INFINITY => "-inf  |";
+        "%06f", f64::INFINITY => "   inf";
+        "%+f", f64::INFINITY => "+inf";
+        "%a", f64::INFINITY => "inf";
+        "%f", -f64::NAN => "-nan";
+        "% f", f64::NAN => " nan";
+    }
+}
+
+#[test]
+fn growing_output() {
+    let mut out = Vec::new();
+    let len = unsafe { format(&mut out, c"%5000d|%s".as_ptr(), 7i32, c"end".as_ptr()) };
+    assert_eq!(len, 5004);
+    assert_eq!(out.len(), 5004);
+    assert!(out[..4999].iter().all(|b| *b == b' '));
+    assert_eq!(&out[4999..], b"7|end");
+
+    let mut long = vec![b'x'; 10000];
+    long.push(0);
+    let mut out = Vec::new();
+    let len = unsafe { format(&mut out, c"%s%%".as_ptr(), long.as_ptr()) };
+    assert_eq!(len, 10001);
+    assert_eq!(&out[..10000], &long[..10000]);
+    assert_eq!(out[10000], b'%');
+}
+
+#[test]
+fn truncated_output() {
+    let mut buf = [0u8; 4];
+    let mut writer = BufWriter::new(&mut buf);
+    let args = [Arg::Int(123456)];
+    let result = unsafe { printf_to(&mut writer, b"n=%d!", args.iter(), &()) };
+    assert_eq!(result, Ok(9));
+    assert_eq!(writer.stored(), 4);
+    assert_eq!(&buf, b"n=12");
+
+    let mut writer = BufWriter::new(&mut []);
+    let result = unsafe {
+        printf_to(
+            &mut writer,
+            b"%s",
+            [Arg::Pointer(c"hello".as_ptr().cast())].iter(),
+            &(),
+        )
+    };
+    assert_eq!(result, Ok(5));
+    assert_eq!(writer.stored(), 0);
+}
+
+/// Accepts `limit` bytes, past them it fails if `fail` is set and accepts nothing otherwise.
+struct LimitedWriter {
+    limit: usize,
+    fail: bool,
+}
+
+impl CWriter for LimitedWriter {
+    type Error = i32;
+    fn write_bytes(&mut self, bytes: &[u8]) -> Result<usize, i32> {
+        if self.limit == 0 {
+            return if self.fail { Err(5) } else { Ok(0) };
+        }
+
+        let amount = self.limit.min(bytes.len());
+        self.limit -= amount;
+        Ok(amount)
+    }
+}
+
+#[test]
+fn output_errors() {
+    let args = [Arg::Int(42)];
+    let mut writer = LimitedWriter {
+        limit: 3,
+        fail: true,
+    };
+    let result = unsafe { printf_to(&mut writer, b"abc%d", args.iter(), &()) };
+    assert_eq!(result, Err(Error::Io(5)));
+
+    let mut writer = LimitedWriter {
+        limit: 3,
+        fail: false,
+    };
+    let result = unsafe { printf_to(&mut writer, b"ab%d", args.iter(), &()) };
+    assert_eq!(result, Err(Error::WriteZero));
+}
+
+#[test]
+fn long_double_floats() {
+    let mut out = Vec::new();
+    for fmt in [&b"%Lf"[..], b"%d %Le", b"%1$La"] {
+        let args = [Arg::Int(1), Arg::Double(1.5)];
+        let result = unsafe { printf_to(&mut out, fmt, args.iter(), &()) };
+        assert_eq!(result, Err(Error::InvalidFormat), "format {fmt:?}");
+    }
+}
+
+/// Formats an `int` as `<value>`.
+unsafe extern "C" fn angled(
+    buf: *mut c_char,
+    n: usize,
+    _: *const SafaPrintfInfo,
+    args: *const *const c_void,
+) -> c_int {
+    let value = unsafe { *(*args).cast::<c_int>() };
+    let text = format!("<{value}>");
+
+    let buf = unsafe { core::slice::from_raw_parts_mut(buf.cast::<u8>(), n) };
+    let len = text.len().min(n - 1);
+    buf[..len].copy_from_slice(&text.as_bytes()[..len]);
+    buf[len] = 0;
+    text.len() as c_int
+}
+
+unsafe extern "C" fn one_int(
+    _: *const SafaPrintfInfo,
+    n: usize,
+    argtypes: *mut c_int,
+    _: *mut c_int,
+) -> c_int {
+    if n > 0 {
+        unsafe { *argtypes = PA_INT };
+    }
+    1
+}
+
+#[test]
+fn custom_conversions() {
+    let specifiers = vec![(b'W', unsafe { CustomSpecifier::new(angled, one_int) })];
+    let args = [Arg::Int(7), Arg::Int(42)];
+
+    let mut out = Vec::new();
+    let len = unsafe { printf_to(&mut out, b"%5W|%-5W|", args.iter(), &specifiers) };
+    assert_eq!(String::from_utf8_lossy(&out), "  <7>|<42> |");
+    assert_eq!(len, Ok(12));
+
+    let mut out = Vec::new();
+    let len = unsafe { printf_to(&mut out, b"%2$W %1$d", args.iter(), &specifiers) };
+    assert_eq!(String::from_utf8_lossy(&out), "<42> 7");
+    assert_eq!(len, Ok(6));
+}
+
+/// Formats as many `x`s as its `int` argument.
+unsafe extern "C" fn repeated(
+    buf: *mut c_char,
+    n: usize,
+    _: *const SafaPrintfInfo,
+    args: *const *const c_void,
+) -> c_int {
+    let count = unsafe { *(*args).cast::<c_int>() } as usize;
+    let buf = unsafe { core::slice::from_raw_parts_mut(buf.cast::<u8>(), n) };
+    let len = count.min(n - 1);
+    buf[..len].fill(b'x');
+    buf[len] = 0;
+    count as c_int
+}
+
+unsafe extern "C" fn failing(
+    _: *mut c_char,
+    _: usize,
+    _: *const SafaPrintfInfo,
+    _: *const *const c_void,
+) -> c_int {
+    -1
+}
+
+unsafe extern "C" fn one_long_double(
+    _: *const SafaPrintfInfo,
+    n: usize,
+    argtypes: *mut c_int,
+    _: *mut c_int,
+) -> c_int {
+    if n > 0 {
+        unsafe { *argtypes = PA_DOUBLE | PA_FLAG_LONG_DOUBLE };
+    }
+    1
+}
+
+unsafe extern "C" fn failing_arginfo(
+    _: *const SafaPrintfInfo,
+    _: usize,
+    _: *mut c_int,
+    _: *mut c_int,
+) -> c_int {
+    -1
+}
+
+#[test]
+fn custom_conversion_overrides_and_failures() {
+    // Custom conversions are looked up before the standard ones
+    let specifiers = vec![(b'd', unsafe { CustomSpecifier::new(angled, one_int) })];
+    let mut out = Vec::new();
+    let len = unsafe {
+        printf_to(
+            &mut out,
+            b"%d %x",
+            [Arg::Int(7), Arg::Int(255)].iter(),
+            &specifiers,
+        )
+    };
+    assert_eq!(String::from_utf8_lossy(&out), "<7> ff");
+    assert_eq!(len, Ok(6));
+
+    // Longer than the buffer the handler is first given
+    let specifiers = vec![(b'R', unsafe { CustomSpecifier::new(repeated, one_int) })];
+    let mut out = Vec::new();
+    let len = unsafe { printf_to(&mut out, b"%R|", [Arg::Int(200)].iter(), &specifiers) };
+    assert_eq!(len, Ok(201));
+    assert_eq!(out[..200], [b'x'; 200]);
+
+    let specifiers = vec![
+        (b'F', unsafe { CustomSpecifier::new(failing, one_int) }),
+        (b'Q', unsafe {
+            CustomSpecifier::new(angled, one_long_double)
+        }),
+        (b'A', unsafe {
+            CustomSpecifier::new(angled, failing_arginfo)
+        }),
+    ];
+    for (fmt, expected) in [
+        (&b"%F"[..], Error::CustomFailed),
+        (b"%Q", Error::InvalidFormat),
+        (b"%A", Error::InvalidFormat),
+        (b"%1$F", Error::CustomFailed),
+        (b"%1$Q", Error::InvalidFormat),
+    ] {
+        let mut out = Vec::new();
+        let result = unsafe { printf_to(&mut out, fmt, [Arg::Int(1)].iter(), &specifiers) };
+        assert_eq!(
+            result,
+            Err(expected),
+            "format {:?}",
+            String::from_utf8_lossy(fmt)
+        );
+    }
+}
+
+/// Formats the sum of as many `int`s as the field width.
+unsafe extern "C" fn sum(
+    buf: *mut c_char,
+    n: usize,
+    info: *const SafaPrintfInfo,
+    args: *const *const c_void,
+) -> c_int {
+    let count = unsafe { (*info).width } as usize;
+    let args = unsafe { core::slice::from_raw_parts(args, count) };
+    let total: c_int = args.iter().map(|arg| unsafe { *arg.cast::<c_int>() }).sum();
+    let text = format!("{total}");
+
+    let buf = unsafe { core::slice::from_raw_parts_mut(buf.cast::<u8>(), n) };
+    let len = text.len().min(n - 1);
+    buf[..len].copy_from_slice(&text.as_bytes()[..len]);
+    buf[len] = 0;
+    text.len() as c_int
+}
+
+unsafe extern "C" fn width_ints(
+    info: *const SafaPrintfInfo,
+    n: usize,
+    argtypes: *mut c_int,
+    _: *mut c_int,
+) -> c_int {
+    let count = unsafe { (*info).width } as usize;
+    for i in 0..count.min(n) {
+        unsafe { *argtypes.add(i) = PA_INT };
+    }
+    count as c_int
+}
+
+#[test]
+fn custom_conversions_depending_on_the_width() {
+    let specifiers = vec![(b'V', unsafe { CustomSpecifier::new(sum, width_ints) })];
+    let args = [Arg::Int(7), Arg::Int(1), Arg::Int(2), Arg::Int(3)];
+
+    let mut out = Vec::new();
+    let len = unsafe { printf_to(&mut out, b"%1$d %2$3V", args.iter(), &specifiers) };
+    assert_eq!(String::from_utf8_lossy(&out), "7   6");
+    assert_eq!(len, Ok(5));
+
+    // The arguments taken depend on a field width that isn't known before the arguments are read
+    let mut out = Vec::new();
+    let args = [Arg::Int(2), Arg::Int(1), Arg::Int(2)];
+    let result = unsafe { printf_to(&mut out, b"%2$*1$V", args.iter(), &specifiers) };
+    assert_eq!(result, Err(Error::InvalidFormat));
+}
@@ -0,0 +1,463 @@
+//! scanf conversions, the expected results of the tables are what glibc 2.36 stores and returns
+//! for the same input and format.
+
+use core::ffi::{c_int, c_long, c_longlong, c_schar, c_short, c_uint, c_void};
+
+use salibc_fmt::Error;
+use salibc_fmt::args::Arg;
+use salibc_fmt::parse::{BufReader, CReader, ScanfError, scanf_from};
+
+/// An object a conversion stores its result in.
+#[derive(Debug, Clone, Copy)]
+enum Out {
+    Int(c_int),
+    UInt(c_uint),
+    Char(c_schar),
+    Short(c_short),
+    Long(c_long),
+    LongLong(c_longlong),
+    Float(f32),
+    Double(f64),
+    /// A buffer for `%s`, `%c` and `%[`, zeroed beforehand
+    Str([u8; 32]),
+    Ptr(usize),
+}
+
+use Out::*;
+
+impl Out {
+    /// Returns the object zeroed.
+    const fn zeroed(self) -> Self {
+        match self {
+            Int(_) => Int(0),
+            UInt(_) => UInt(0),
+            Char(_) => Char(0),
+            Short(_) => Short(0),
+            Long(_) => Long(0),
+            LongLong(_) => LongLong(0),
+            Float(_) => Float(0.),
+            Double(_) => Double(0.),
+            Str(_) => Str([0; 32]),
+            Ptr(_) => Ptr(0),
+        }
+    }
+
+    fn as_arg(&mut self) -> Arg {
+        let ptr: *mut c_void = match self {
+            Int(v) => (v as *mut c_int).cast(),
+            UInt(v) => (v as *mut c_uint).cast(),
+            Char(v) => (v as *mut c_schar).cast(),
+            Short(v) => (v as *mut c_short).cast(),
+            Long(v) => (v as *mut c_long).cast(),
+            LongLong(v) => (v as *mut c_longlong).cast(),
+            Float(v) => (v as *mut f32).cast(),
+            Double(v) => (v as *mut f64).cast(),
+            Str(v) => v.as_mut_ptr().cast(),
+            Ptr(v) => (v as *mut usize).cast(),
+        };
+        Arg::Pointer(ptr)
+    }
+}
+
+/// Floating point results are compared bitwise so that the sign of zeroes matters and NaNs compare equal.
+impl PartialEq for Out {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Float(a), Float(b)) => a.to_bits() == b.to_bits(),
+            (Double(a), Double(b)) => a.to_bits() == b.to_bits(),
+            (Int(a), Int(b)) => a == b,
+            (UInt(a), UInt(b)) => a == b,
+            (Char(a), Char(b)) => a == b,
+            (Short(a), Short(b)) => a == b,
+            (Long(a), Long(b)) => a == b,
+            (LongLong(a), LongLong(b)) => a == b,
+            (Str(a), Str(b)) => a == b,
+            (Ptr(a), Ptr(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+/// The contents of a string buffer.
+const fn s(text: &[u8]) -> Out {
+    let mut buf = [0; 32];
+    let mut i = 0;
+    while i < text.len() {
+        buf[i] = text[i];
+        i += 1;
+    }
+    Str(buf)
+}
+
+/// Scans `input` as `sscanf` does, storing the results in zeroed objects of the same types as `expected`,
+/// returns what `sscanf` returns and the objects.
+fn scan(input: &str, fmt: &str, expected: &[Out]) -> (c_int, Vec<Out>) {
+    let mut outs: Vec<Out> = expected.iter().map(|out| out.zeroed()).collect();
+    let args: Vec<Arg> = outs.iter_mut().map(Out::as_arg).collect();
+
+    let mut reader = BufReader::new(input.as_bytes());
+    let result = unsafe { scanf_from(&mut reader, fmt.as_bytes(), args.iter()) };
+    let returned = match result {
+        Ok((_, Some(assigned))) => assigned as c_int,
+        Ok((_, None)) => -1,
+        Err(_) => -1,
+    };
+    (returned, outs)
+}
+
+/// Checks that scanning each input with the format returns and stores the expected results.
+macro_rules! check {
+    ($($input:literal, $fmt:literal => $returned:expr, [$($out:expr),*];)*) => {$(
+        let expected = [$($out),*];
+        let (returned, outs) = scan($input, $fmt, &expected);
+        assert_eq!(returned, $returned, "input {:?} format {:?}", $input, $fmt);
+        assert_eq!(outs, expected, "input {:?} format {:?}", $input, $fmt);
+    )*};
+}
+
+#[test]
+fn integers() {
+    check! {
+        "42", "%d" => 1, [Int(42)];
+        "-17", "%d" => 1, [Int(-17)];
+        "ff", "%x" => 1, [UInt(255)];
+        "0XfF", "%x" => 1, [UInt(255)];
+        "0x", "%x%s" => 1, [UInt(0), s(b"")];
+        "4294967295", "%u" => 1, [UInt(4294967295)];
+        "-1", "%u" => 1, [UInt(4294967295)];
+        "12abc", "%d%s" => 2, [Int(12), s(b"abc")];
+    }
+}
+
+#[test]
+fn integer_bases() {
+    check! {
+        "+5", "%i" => 1, [Int(5)];
+        "0x1f", "%i" => 1, [Int(31)];
+        "017", "%i" => 1, [Int(15)];
+        "0", "%i" => 1, [Int(0)];
+        "08", "%i" => 1, [Int(0)];
+        "-077 0x", "%i %i" => 2, [Int(-63), Int(0)];
+        "0x10 077", "%i %o" => 2, [Int(16), UInt(63)];
+        "777", "%o" => 1, [UInt(511)];
+        "-10", "%o" => 1, [UInt(4294967288)];
+        "8", "%o" => 0, [UInt(0)];
+    }
+}
+
+#[test]
+fn integer_lengths() {
+    check! {
+        "300", "%hhd" => 1, [Char(44)];
+        "70000", "%hd" => 1, [Short(4464)];
+        "9223372036854775807", "%ld" => 1, [Long(9223372036854775807)];
+        "-9223372036854775808", "%lld" => 1, [LongLong(-9223372036854775808)];
+        "18446744073709551615", "%llu" => 1, [LongLong(-1)];
+        "-12", "%zd" => 1, [Long(-12)];
+        "34", "%jd" => 1, [LongLong(34)];
+        "-56", "%td" => 1, [Long(-56)];
+    }
+}
+
+#[test]
+fn field_widths() {
+    check! {
+        "123456", "%3d%d" => 2, [Int(123), Int(456)];
+        "-12345", "%3d%d" => 2, [Int(-12), Int(345)];
+        "+", "%1d" => 0, [Int(0)];
+        "0x1f", "%2x%s" => 2, [UInt(0), s(b"1f")];
+        "hello", "%3s" => 1, [s(b"hel")];
+        "abcdef", "%2s%3s" => 2, [s(b"ab"), s(b"cde")];
+        "1234.5678", "%4lf%lf" => 2, [Double(1234.0), Double(0.5678)];
+        "12.5e3", "%4lf%s" => 2, [Double(12.5), s(b"e3")];
+    }
+}
+
+#[test]
+fn matching_and_input_failures() {
+    check! {
+        "abc", "%d" => 0, [Int(0)];
+        "", "%d" => -1, [Int(0)];
+        "12", "%d %d" => 1, [Int(12), Int(0)];
+        "12 x", "%d %d" => 1, [Int(12), Int(0)];
+        "-", "%d" => 0, [Int(0)];
+        "", "%s" => -1, [s(b"")];
+        "a", "x%s" => 0, [s(b"")];
+        "5x", "%dy" => 1, [Int(5)];
+        "a", "a%d" => -1, [Int(0)];
+        " a1", "a%d" => 0, [Int(0)];
+    }
+}
+
+#[test]
+fn whitespace() {
+    check! {
+        "  12  34", "%d%d" => 2, [Int(12), Int(34)];
+        " \t\n 7", "%d" => 1, [Int(7)];
+        "\n\n1.5", "%lf" => 1, [Double(1.5)];
+        "   ", "%d" => -1, [Int(0)];
+        "   ", "%s" => -1, [s(b"")];
+        "1\n\t 2", "%d %d" => 2, [Int(1), Int(2)];
+        "5 ,6", "%d ,%d" => 2, [Int(5), Int(6)];
+        "5,6", "%d ,%d" => 2, [Int(5), Int(6)];
+        "5 6", "%d\t%d" => 2, [Int(5), Int(6)];
+        " x", " %c" => 1, [s(b"x")];
+        "  x", "  %c" => 1, [s(b"x")];
+        " a1", " a%d" => 1, [Int(1)];
+    }
+}
+
+#[test]
+fn floats() {
+    check! {
+        "2.5", "%f" => 1, [Float(2.5)];
+        "2.5", "%lf" => 1, [Double(2.5)];
+        "-1e10", "%lf" => 1, [Double(-10000000000.0)];
+        "1e-5", "%le" => 1, [Double(1e-05)];
+        ".5", "%lf" => 1, [Double(0.5)];
+        "5.", "%lf" => 1, [Double(5.0)];
+        "0x1.8p1", "%la" => 1, [Double(3.0)];
+        "0x1p-1074", "%lg" => 1, [Double(5e-324)];
+        "inf", "%lf" => 1, [Double(f64::INFINITY)];
+        "-Infinity", "%lf" => 1, [Double(f64::NEG_INFINITY)];
+        "nan", "%lf" => 1, [Double(f64::NAN)];
+        "nan(123)", "%lf" => 1, [Double(f64::NAN)];
+        "1e", "%lf%s" => 1, [Double(1.0), s(b"")];
+        "1.5e+3x", "%lf%s" => 2, [Double(1500.0), s(b"x")];
+        "3.999999999999999999999", "%lf" => 1, [Double(4.0)];
+        "0x1.fffffffffffff8p0", "%la" => 1, [Double(2.0)];
+        "1e400", "%lf" => 1, [Double(f64::INFINITY)];
+        "0", "%lf" => 1, [Double(0.0)];
+        "-0", "%lf" => 1, [Double(-0.0)];
+        "2.5E-3", "%G" => 1, [Float(0.0025)];
+    }
+}
+
+#[test]
+fn strings() {
+    check! {
+        "hello world", "%s %s" => 2, [s(b"hello"), s(b"world")];
+    }
+}
+
+#[test]
+fn chars() {
+    check! {
+        "abc", "%c" => 1, [s(b"a")];
+        "abc", "%2c" => 1, [s(b"ab")];
+        " x", "%c" => 1, [s(b" ")];
+        "xyz", "%c%n" => 1, [s(b"x"), Int(1)];
+    }
+}
+
+#[test]
+fn scansets() {
+    check! {
+        "abc123", "%[a-z]%d" => 2, [s(b"abc"), Int(123)];
+        "]x", "%[]x]" => 1, [s(b"]x")];
+        "a-b", "%[a-]" => 1, [s(b"a-")];
+        "-a-b", "%[-a]%s" => 2, [s(b"-a-"), s(b"b")];
+        "a^b", "%[a^]%s" => 2, [s(b"a^"), s(b"b")];
+        "cxq", "%[a-cx-z]%s" => 2, [s(b"cx"), s(b"q")];
+        "abcdef", "%2[a-z]%s" => 2, [s(b"ab"), s(b"cdef")];
+        "xyz,rest", "%[^,],%s" => 2, [s(b"xyz"), s(b"rest")];
+        "key=value", "%[^=]=%s" => 2, [s(b"key"), s(b"value")];
+        "abc9", "%[^0-9]%s" => 2, [s(b"abc"), s(b"9")];
+        "ab cd", "%[^\n]" => 1, [s(b"ab cd")];
+        "123", "%[a-z]" => 0, [s(b"")];
+        "", "%[a]%s" => -1, [s(b""), s(b"")];
+    }
+}
+
+#[test]
+fn percents_and_counts() {
+    check! {
+        "100%", "%d%%" => 1, [Int(100)];
+        "%x", "%%%s" => 1, [s(b"x")];
+        "  %x", " %%%s" => 1, [s(b"x")];
+        "abc", "%s%n" => 1, [s(b"abc"), Int(3)];
+        "  42", "%n%d%n" => 1, [Int(0), Int(42), Int(4)];
+        "1 2", "%d%*d%n" => 1, [Int(1), Int(3)];
+    }
+}
+
+#[test]
+fn assignment_suppression() {
+    check! {
+        "12 34", "%*d %d" => 1, [Int(34)];
+        "hello 5", "%*s %d" => 1, [Int(5)];
+        "a b c", "%*s %*s %s" => 1, [s(b"c")];
+        "xyz1", "%*[a-z]%d" => 1, [Int(1)];
+        "ab5", "%*2c%d" => 1, [Int(5)];
+        "1234", "%*2d%d" => 1, [Int(34)];
+        "1 x", "%*d %*d" => 0, [];
+        "", "%*d" => -1, [];
+    }
+}
+
+#[test]
+fn literals() {
+    check! {
+        "a=5", "a=%d" => 1, [Int(5)];
+        "b=5", "a=%d" => 0, [Int(0)];
+    }
+}
+
+#[test]
+fn pointers() {
+    check! {
+        "0x1234", "%p" => 1, [Ptr(0x1234)];
+        "deadbeef", "%p" => 1, [Ptr(0xdeadbeef)];
+    }
+}
+
+/// glibc 2.36 predates the C23 `%b` conversion of scanf and binary prefixes for `%i`, these are
+/// checked against what C23 specifies for `strtol` in base 2 and 0 instead.
+#[test]
+fn binary_integers() {
+    check! {
+        "101", "%b" => 1, [UInt(5)];
+        "0b101", "%b" => 1, [UInt(5)];
+        "0B11", "%b" => 1, [UInt(3)];
+        "-1", "%b" => 1, [UInt(4294967295)];
+        "1012", "%b%d" => 2, [UInt(5), Int(2)];
+        "0b101", "%i" => 1, [Int(5)];
+        "11111111", "%hhb" => 1, [Char(-1)];
+        "2", "%b" => 0, [UInt(0)];
+    }
+}
+
+/// glibc 2.36 predates the C23 `wN` and `wfN` lengths, these are checked against the conversions
+/// `include/inttypes.h` uses for the same types instead.
+#[test]
+fn exact_and_fast_widths() {
+    check! {
+        "70000", "%w16d" => 1, [Short(4464)];
+        "-70000", "%w32d" => 1, [Int(-70000)];
+        "-9223372036854775808", "%w64d" => 1, [LongLong(-9223372036854775808)];
+        "-70000", "%wf16d" => 1, [Int(-70000)];
+        "-9223372036854775808", "%wf64d" => 1, [LongLong(-9223372036854775808)];
+    }
+
+    // Followed by another `int` to catch a conversion storing more than an `int_fast32_t`
+    let mut fast: [c_int; 2] = [-1, -1];
+    let args = [Arg::Pointer(fast.as_mut_ptr().cast())];
+    let result = unsafe { scanf_from(&mut BufReader::new(b"5"), b"%wf32d", args.iter()) };
+    assert_eq!(result.ok().and_then(|(_, assigned)| assigned), Some(1));
+    assert_eq!(fast, [5, -1]);
+}
+
+/// `vfscanf` scans a stream through the same reader call after call, each call picks up at the first byte
+/// the previous one didn't consume.
+#[test]
+fn consecutive_calls() {
+    let mut reader = BufReader::new(b"12 abc\n34 end");
+    let mut n: c_int = 0;
+    let mut word = [0u8; 8];
+
+    let args = [Arg::Pointer((&mut n as *mut c_int).cast())];
+    let result = unsafe { scanf_from(&mut reader, b"%d", args.iter()) };
+    assert_eq!(result.ok(), Some((2, Some(1))));
+    assert_eq!(n, 12);
+
+    let args = [Arg::Pointer(word.as_mut_ptr().cast())];
+    let result = unsafe { scanf_from(&mut reader, b"%s", args.iter()) };
+    assert_eq!(result.ok(), Some((4, Some(1))));
+    assert_eq!(&word[..4], b"abc\0");
+
+    let args = [Arg::Pointer((&mut n as *mut c_int).cast())];
+    let result = unsafe { scanf_from(&mut reader, b"%d", args.iter()) };
+    assert_eq!(result.ok(), Some((3, Some(1))));
+    assert_eq!(n, 34);
+
+    // A mismatch leaves the byte it stopped at to the next call
+    let result = unsafe { scanf_from(&mut reader, b"%d", args.iter()) };
+    assert_eq!(result.ok(), Some((1, Some(0))));
+    let args = [Arg::Pointer(word.as_mut_ptr().cast())];
+    let result = unsafe { scanf_from(&mut reader, b"%s", args.iter()) };
+    assert_eq!(result.ok(), Some((3, Some(1))));
+    assert_eq!(&word[..4], b"end\0");
+
+    let result = unsafe { scanf_from(&mut reader, b"%s", args.iter()) };
+    assert_eq!(result.ok(), Some((0, None)));
+}
+
+/// `long double`s are read with the precision of a `double`, these inputs are exact `double`s.
+#[test]
+#[cfg(target_arch = "x86_64")]
+fn long_double_floats() {
+    for (input, fmt, expected) in [
+        ("1.5", "%Lf", 0x3fffc000000000000000),
+        ("-2", "%Lf", 0xc0008000000000000000),
+        ("0", "%Lf", 0x00000000000000000000),
+        ("-0", "%Le", 0x80000000000000000000),
+        ("0x1p-1074", "%Lg", 0x3bcd8000000000000000),
+        ("0x1.fffffffffffffp+1023", "%La", 0x43fefffffffffffff800),
+        ("-inf", "%Lf", 0xffff8000000000000000),
+        ("nan", "%Lf", 0x7fffc000000000000000),
+        ("0.25", "%LG", 0x3ffd8000000000000000),
+        ("0x1.8p-1030", "%LA", 0x3bf9c000000000000000),
+    ] {
+        let mut value: u128 = 0;
+        let args = [Arg::Pointer((&raw mut value).cast())];
+        let result = unsafe {
+            scanf_from(
+                &mut BufReader::new(input.as_bytes()),
+                fmt.as_bytes(),
+                args.iter(),
+            )
+        };
+        assert_eq!(
+            result.ok().and_then(|(_, assigned)| assigned),
+            Some(1),
+            "input {input:?}"
+        );
+        // Only the low 80 bits are part of the value
+        assert_eq!(value & ((1 << 80) - 1), expected, "input {input:?}");
+    }
+}
+
+/// Reads its input then fails.
+struct FailingReader(&'static [u8], usize);
+
+impl CReader for FailingReader {
+    type Error = ();
+    fn read_bytes(&mut self, buf: &mut [u8]) -> Result<usize, ()> {
+        let rest = &self.0[self.1..];
+        if rest.is_empty() {
+            return Err(());
+        }
+
+        let len = rest.len().min(buf.len());
+        buf[..len].copy_from_slice(&rest[..len]);
+        self.1 += len;
+        Ok(len)
+    }
+
+    fn unread_byte(&mut self, _: u8) {
+        self.1 -= 1;
+    }
+}
+
+#[test]
+fn read_errors() {
+    let mut values: [c_int; 2] = [0, 0];
+    let args = [
+        Arg::Pointer((&raw mut values[0]).cast()),
+        Arg::Pointer((&raw mut values[1]).cast()),
+    ];
+    let result = unsafe { scanf_from(&mut FailingReader(b"12 ", 0), b"%d %d", args.iter()) };
+    let error = ScanfError {
+        error: Error::Io(()),
+        assigned: 1,
+    };
+    assert_eq!(result, Err(error));
+    assert_eq!(values, [12, 0]);
+
+    let result = unsafe { scanf_from(&mut FailingReader(b"", 0), b"%d %d", args.iter()) };
+    let error = ScanfError {
+        error: Error::Io(()),
+        assigned: 0,
+    };
+    assert_eq!(result, Err(error));
+}
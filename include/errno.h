@@ -22,6 +22,7 @@ int* __errno_location(void);
 #define ENOTEMPTY            0x12  /* DirectoryNotEmpty */
 #define EBUSY                0x15  /* Busy */
 #define ENOMEM               0x17  /* OutOfMemory */
+#define EMFILE               ENOMEM /* Alias, no descriptor is left below OPEN_MAX */
 #define ETIMEDOUT            0x19  /* Timeout */
 #define EPIPE                0x20  /* ConnectionClosed */
 #define ECONNREFUSED         0x21  /* ConnectionRefused */
@@ -58,3 +59,4 @@ int* __errno_location(void);
 #define EINVALIDSIZE         0x27
 #define EFORCETERMINATED     0x28
 #define EINTR                EFORCETERMINATED
+#define EBADF                EUNKNOWNRESOURCE
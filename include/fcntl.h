@@ -0,0 +1,21 @@
+#pragma once
+
+#define O_RDONLY 0
+#define O_WRONLY 1
+#define O_RDWR 2
+#define O_ACCMODE 3
+
+#define O_CREAT 0100
+#define O_EXCL 0200
+#define O_TRUNC 01000
+#define O_APPEND 02000
+#define O_NONBLOCK 04000
+#define O_CLOEXEC 02000000
+
+#define F_DUPFD 0
+#define F_GETFL 3
+#define F_SETFL 4
+
+/* The permissions given when creating a file are ignored */
+int open(const char *path, int flags, ...);
+int fcntl(int fd, int cmd, ...);
@@ -31,6 +31,9 @@
 #define PAGE_SIZE 4096
 #endif
 
+// Other Invariant Values
+#define NL_ARGMAX                4096
+
 // Pathname Variable Values
 #define FILESIZEBITS             64
 #define LINK_MAX                 _POSIX_LINK_MAX
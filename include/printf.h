@@ -0,0 +1,47 @@
+#pragma once
+#include <stddef.h>
+
+/* SafaOS custom printf conversions, modeled after glibc's register_printf_specifier but not compatible with it,
+   hence the safa_ prefix. Only the PA_* argument types match glibc's. */
+
+struct safa_printf_info {
+    int prec;                    /* -1 if no precision was given */
+    int width;                   /* 0 if no field width was given */
+    int spec;                    /* the conversion specifier */
+    int pad;                     /* '0' for the 0 flag, ' ' otherwise */
+    unsigned char left;          /* - flag */
+    unsigned char showsign;      /* + flag */
+    unsigned char space;         /* ' ' flag */
+    unsigned char alt;           /* # flag */
+    unsigned char is_char;       /* hh */
+    unsigned char is_short;      /* h */
+    unsigned char is_long;       /* l */
+    unsigned char is_long_double;/* ll or L */
+};
+
+enum {
+    PA_INT,
+    PA_CHAR,
+    PA_WCHAR,
+    PA_STRING,
+    PA_WSTRING,
+    PA_POINTER,
+    PA_FLOAT,
+    PA_DOUBLE,
+};
+
+#define PA_FLAG_MASK 0xff00
+#define PA_FLAG_LONG_LONG (1 << 8)
+#define PA_FLAG_LONG_DOUBLE PA_FLAG_LONG_LONG
+#define PA_FLAG_LONG (1 << 9)
+#define PA_FLAG_SHORT (1 << 10)
+#define PA_FLAG_PTR (1 << 11)
+
+/* The conversion is written snprintf-style into buf, the result is then padded to the field width by printf.
+   Returns the length of the whole conversion or a negative value on failure. */
+typedef int safa_printf_function(char *buf, size_t n, const struct safa_printf_info *info, const void *const *args);
+/* Stores the PA_* types of the arguments taken by the conversion into argtypes (up to n of them),
+   returns the amount of arguments taken. */
+typedef int safa_printf_arginfo_function(const struct safa_printf_info *info, size_t n, int *argtypes, int *size);
+
+int safa_register_printf_specifier(int spec, safa_printf_function *handler, safa_printf_arginfo_function *arginfo);
@@ -7,6 +7,7 @@
 #define BUFSIZ 4096
 
 typedef intptr_t ssize_t;
+typedef int64_t off_t;
 
 typedef struct FILE FILE;
 extern FILE* stdout;
@@ -14,34 +15,72 @@ extern FILE* stdin ;
 extern FILE* stderr;
 
 int printf(const char* fmt, ...) __attribute__((format(printf,1,2)));
+int vprintf(const char* fmt, va_list args);
 int fprintf(FILE* f, const char* fmt, ...) __attribute__((format(printf,2,3)));
 int vfprintf(FILE* f, const char* fmt, va_list args);
+int dprintf(int fd, const char* fmt, ...) __attribute__((format(printf,2,3)));
+int vdprintf(int fd, const char* fmt, va_list args);
+int asprintf(char** strp, const char* fmt, ...) __attribute__((format(printf,2,3)));
+int vasprintf(char** strp, const char* fmt, va_list args);
 int snprintf(char* buf, size_t cap, const char* fmt, ...) __attribute__((format(printf,3,4)));
 int vsnprintf(char* buf, size_t cap, const char* fmt, va_list args);
 int vsprintf(char *buf, const char *fmt, va_list args);
 int sprintf(char* buf, const char* fmt, ...) __attribute__((format(printf,2, 3)));
 
+int scanf(const char* fmt, ...) __attribute__((format(scanf,1,2)));
+int vscanf(const char* fmt, va_list args);
 int fscanf(FILE* f, const char* fmt, ...) __attribute__((format(scanf,2,3)));
+int vfscanf(FILE* f, const char* fmt, va_list args);
 int sscanf(const char* s, const char* fmt, ...) __attribute__((format(scanf,2,3)));
+int vsscanf(const char* s, const char* fmt, va_list args);
 
 FILE* fopen(const char *path, const char *mode);
 FILE *freopen(const char *path, const char *mode, FILE *stream);
 FILE *fdopen(int fildes, const char *mode);
+FILE *fmemopen(void *buf, size_t size, const char *mode);
+FILE *open_memstream(char **bufp, size_t *sizep);
+
+typedef ssize_t cookie_read_function_t(void *cookie, char *buf, size_t size);
+typedef ssize_t cookie_write_function_t(void *cookie, const char *buf, size_t size);
+typedef int cookie_seek_function_t(void *cookie, off_t *offset, int whence);
+typedef int cookie_close_function_t(void *cookie);
+typedef struct {
+    cookie_read_function_t *read;   /* reads reach end of file right away if NULL */
+    cookie_write_function_t *write; /* writes are discarded if NULL */
+    cookie_seek_function_t *seek;   /* seeking fails if NULL */
+    cookie_close_function_t *close;
+} cookie_io_functions_t;
+FILE *fopencookie(void *cookie, const char *mode, cookie_io_functions_t io_funcs);
+FILE *funopen(const void *cookie,
+              int (*readfn)(void *, char *, int),
+              int (*writefn)(void *, const char *, int),
+              off_t (*seekfn)(void *, off_t, int),
+              int (*closefn)(void *));
+int fileno(FILE *f);
 
 int fclose(FILE *f);
 
 size_t fwrite(const void *buffer, size_t size, size_t count, FILE *f);
+size_t fwrite_unlocked(const void *buffer, size_t size, size_t count, FILE *f);
 size_t fread(void *buffer, size_t size, size_t count, FILE *f);
 
-ssize_t ftell(FILE *f);
+long ftell(FILE *f);
+off_t ftello(FILE *f);
 
 int fgetc(FILE *f);
 #define getc(f) fgetc(f)
+int getc_unlocked(FILE *f);
+int getchar_unlocked(void);
 int ungetc(int c, FILE *f);
 char *fgets(char *buf, size_t size, FILE *f);
+char *fgets_unlocked(char *buf, size_t size, FILE *f);
+ssize_t getdelim(char **lineptr, size_t *n, int delim, FILE *f);
+ssize_t getline(char **lineptr, size_t *n, FILE *f);
 
 #define putc(c, f) fputc(c, f)
 int fputc(int c, FILE *f);
+int putc_unlocked(int c, FILE *f);
+int putchar_unlocked(int c);
 
 int fputs(const char *str, FILE* stream);
 int puts(const char *str);
@@ -52,6 +91,14 @@ int fflush(FILE* f);
 #define SEEK_CUR 1
 #define SEEK_END 2
 int fseek(FILE *f, long offset, int whence);
+int fseeko(FILE *f, off_t offset, int whence);
+void rewind(FILE *f);
+
+typedef struct {
+    off_t __pos;
+} fpos_t;
+int fgetpos(FILE *f, fpos_t *pos);
+int fsetpos(FILE *f, const fpos_t *pos);
 
 int getchar();
 int putchar(int c);
@@ -64,6 +111,10 @@ void clearerr(FILE *f);
 int ferror(FILE *f);
 int feof(FILE *f);
 
+void flockfile(FILE *f);
+int ftrylockfile(FILE *f);
+void funlockfile(FILE *f);
+
 #define FILENAME_MAX 1024
 
 #define _IONBF 0
@@ -78,7 +129,11 @@ static int setbuf(FILE *stream, char *buffer) {
         return setvbuf(stream, NULL, _IONBF, 0);
 
 }
+void setbuffer(FILE *stream, char *buffer, size_t size);
+void setlinebuf(FILE *stream);
 
+#define P_tmpdir "ram:/tmp"
+#define TMP_MAX 238328
 FILE* tmpfile();
 char* tmpnam(char *s);
 
@@ -48,3 +48,7 @@ unsigned long      strtoul ( const char* str, char** str_end, int base );
 unsigned long long strtoull( const char* str, char** str_end, int base );
 void *bsearch(const void *key, const void *base, size_t nmemb, size_t size,
               int (*compar)(const void *, const void *));
+
+int mkstemp(char *template);
+int mkstemps(char *template, int suffixlen);
+char *mkdtemp(char *template);
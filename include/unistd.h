@@ -1,3 +1,24 @@
 #pragma once
+#include <stddef.h>
+#include <stdint.h>
+
+typedef intptr_t ssize_t;
+typedef int64_t off_t;
+
+#define STDIN_FILENO 0
+#define STDOUT_FILENO 1
+#define STDERR_FILENO 2
+
+#define SEEK_SET 0
+#define SEEK_CUR 1
+#define SEEK_END 2
+
 int _exit(int code);
 int rmdir(const char *path);
+
+ssize_t read(int fd, void *buf, size_t count);
+ssize_t write(int fd, const void *buf, size_t count);
+int close(int fd);
+off_t lseek(int fd, off_t offset, int whence);
+int dup(int fd);
+int dup2(int old_fd, int new_fd);
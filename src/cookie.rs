@@ -0,0 +1,219 @@
+//! [`File`](crate::file::File) backends over caller provided callbacks, see `fopencookie` and `funopen` in `include/stdio.h`.
+
+use core::ffi::{c_char, c_int, c_void};
+
+use safa_api::errors::ErrorStatus;
+
+use crate::{
+    errno::get_error,
+    file::{FileBackend, SeekPosition},
+    stdio::{OffT, SEEK_CUR, SEEK_END, SEEK_SET},
+};
+
+pub type CookieReadFunction =
+    unsafe extern "C" fn(cookie: *mut c_void, buf: *mut c_char, size: usize) -> isize;
+pub type CookieWriteFunction =
+    unsafe extern "C" fn(cookie: *mut c_void, buf: *const c_char, size: usize) -> isize;
+/// Seeks to `*offset` relative to `whence` and stores the resulting position in `*offset`, returns 0 on success.
+pub type CookieSeekFunction =
+    unsafe extern "C" fn(cookie: *mut c_void, offset: *mut OffT, whence: c_int) -> c_int;
+pub type CookieCloseFunction = unsafe extern "C" fn(cookie: *mut c_void) -> c_int;
+
+/// The callbacks given to `fopencookie`, any of them may be null.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct CookieIoFunctions {
+    /// Reads reach the end of the file right away if null
+    pub read: Option<CookieReadFunction>,
+    /// Writes are discarded if null
+    pub write: Option<CookieWriteFunction>,
+    /// Seeking fails if null
+    pub seek: Option<CookieSeekFunction>,
+    pub close: Option<CookieCloseFunction>,
+}
+
+pub type FunReadFunction =
+    unsafe extern "C" fn(cookie: *mut c_void, buf: *mut c_char, size: c_int) -> c_int;
+pub type FunWriteFunction =
+    unsafe extern "C" fn(cookie: *mut c_void, buf: *const c_char, size: c_int) -> c_int;
+/// Seeks to `offset` relative to `whence`, returns the resulting position or -1 on failure.
+pub type FunSeekFunction =
+    unsafe extern "C" fn(cookie: *mut c_void, offset: OffT, whence: c_int) -> OffT;
+pub type FunCloseFunction = unsafe extern "C" fn(cookie: *mut c_void) -> c_int;
+
+/// The callbacks given to `funopen`, any of them may be null.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct FunIoFunctions {
+    pub(crate) read: Option<FunReadFunction>,
+    pub(crate) write: Option<FunWriteFunction>,
+    pub(crate) seek: Option<FunSeekFunction>,
+    pub(crate) close: Option<FunCloseFunction>,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Callbacks {
+    Cookie(CookieIoFunctions),
+    Fun(FunIoFunctions),
+}
+
+/// A stream calling back into the caller with an opaque cookie for each operation.
+#[derive(Debug)]
+pub(crate) struct CookieFile {
+    cookie: *mut c_void,
+    callbacks: Callbacks,
+    readable: bool,
+    writable: bool,
+    append: bool,
+}
+
+// The cookie is only passed back to the callbacks, whose thread safety is up to the caller
+unsafe impl Send for CookieFile {}
+unsafe impl Sync for CookieFile {}
+
+impl CookieFile {
+    /// Creates a stream over the callbacks given to `fopencookie`.
+    pub(crate) fn from_cookie(
+        cookie: *mut c_void,
+        callbacks: CookieIoFunctions,
+        readable: bool,
+        writable: bool,
+    ) -> Self {
+        Self {
+            cookie,
+            callbacks: Callbacks::Cookie(callbacks),
+            readable,
+            writable,
+            append: false,
+        }
+    }
+
+    /// Creates a stream over the callbacks given to `funopen`,
+    /// the stream is readable if there is a read callback and writable if there is a write callback.
+    pub(crate) fn from_fun(cookie: *mut c_void, callbacks: FunIoFunctions) -> Self {
+        Self {
+            cookie,
+            callbacks: Callbacks::Fun(callbacks),
+            readable: callbacks.read.is_some(),
+            writable: callbacks.write.is_some(),
+            append: false,
+        }
+    }
+
+    /// Seeks to `offset` relative to `whence` with the seek callback, returns the resulting position.
+    fn seek_raw(&mut self, offset: OffT, whence: c_int) -> Result<usize, ErrorStatus> {
+        let pos = match self.callbacks {
+            Callbacks::Cookie(CookieIoFunctions {
+                seek: Some(seek), ..
+            }) => {
+                let mut offset = offset;
+                if unsafe { seek(self.cookie, &mut offset, whence) } != 0 {
+                    return Err(get_error());
+                }
+                offset
+            }
+            Callbacks::Fun(FunIoFunctions {
+                seek: Some(seek), ..
+            }) => unsafe { seek(self.cookie, offset, whence) },
+            _ => return Err(ErrorStatus::OperationNotSupported),
+        };
+
+        usize::try_from(pos).map_err(|_| get_error())
+    }
+}
+
+impl FileBackend for CookieFile {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, ErrorStatus> {
+        if !self.readable {
+            return Err(ErrorStatus::UnknownResource);
+        }
+
+        let read = match self.callbacks {
+            Callbacks::Cookie(CookieIoFunctions {
+                read: Some(read), ..
+            }) => unsafe { read(self.cookie, buf.as_mut_ptr().cast(), buf.len()) },
+            Callbacks::Fun(FunIoFunctions {
+                read: Some(read), ..
+            }) => {
+                let size = buf.len().min(c_int::MAX as usize) as c_int;
+                unsafe { read(self.cookie, buf.as_mut_ptr().cast(), size) as isize }
+            }
+            _ => 0,
+        };
+
+        usize::try_from(read).map_err(|_| get_error())
+    }
+
+    fn write(&mut self, bytes: &[u8]) -> Result<usize, ErrorStatus> {
+        if !self.writable {
+            return Err(ErrorStatus::UnknownResource);
+        }
+
+        if self.append {
+            // Without a seek callback the cookie decides where writes land
+            _ = self.seek_raw(0, SEEK_END);
+        }
+
+        let written = match self.callbacks {
+            Callbacks::Cookie(CookieIoFunctions {
+                write: Some(write), ..
+            }) => unsafe { write(self.cookie, bytes.as_ptr().cast(), bytes.len()) },
+            Callbacks::Fun(FunIoFunctions {
+                write: Some(write), ..
+            }) => {
+                let size = bytes.len().min(c_int::MAX as usize) as c_int;
+                unsafe { write(self.cookie, bytes.as_ptr().cast(), size) as isize }
+            }
+            _ => bytes.len() as isize,
+        };
+
+        match usize::try_from(written) {
+            Ok(0) if !bytes.is_empty() => Err(get_error()),
+            Ok(written) => Ok(written),
+            Err(_) => Err(get_error()),
+        }
+    }
+
+    fn seek(&mut self, wrench: SeekPosition) -> Result<(), ErrorStatus> {
+        let (offset, whence) = match wrench {
+            SeekPosition::Start(s) => (s as OffT, SEEK_SET),
+            SeekPosition::End(e) => (-(e as OffT), SEEK_END),
+            SeekPosition::Current(c) => (c as OffT, SEEK_CUR),
+        };
+
+        self.seek_raw(offset, whence)?;
+        Ok(())
+    }
+
+    fn offset(&mut self) -> Result<usize, ErrorStatus> {
+        self.seek_raw(0, SEEK_CUR)
+    }
+
+    fn size(&mut self) -> Result<usize, ErrorStatus> {
+        let pos = self.seek_raw(0, SEEK_CUR)?;
+        let size = self.seek_raw(0, SEEK_END)?;
+        self.seek_raw(pos as OffT, SEEK_SET)?;
+        Ok(size)
+    }
+
+    fn set_append(&mut self, append: bool) -> Result<(), ErrorStatus> {
+        self.append = append;
+        Ok(())
+    }
+
+    fn close(&mut self) -> Result<(), ErrorStatus> {
+        let results = match self.callbacks {
+            Callbacks::Cookie(CookieIoFunctions {
+                close: Some(close), ..
+            }) => unsafe { close(self.cookie) },
+            Callbacks::Fun(FunIoFunctions {
+                close: Some(close), ..
+            }) => unsafe { close(self.cookie) },
+            _ => 0,
+        };
+
+        if results != 0 {
+            return Err(get_error());
+        }
+        Ok(())
+    }
+}
@@ -10,7 +10,7 @@ use safa_api::{
     syscalls::{self, types::Ri},
 };
 
-use crate::{errno::set_error, file::File, try_errno};
+use crate::{errno::set_error, file::FileState, try_errno};
 
 pub fn entry_name(entry: &DirEnt) -> &str {
     CStr::from_bytes_until_nul(&entry.d_name)
@@ -34,7 +34,7 @@ pub struct Dir {
 
 impl Dir {
     pub fn open(path: &str) -> Result<Self, ErrorStatus> {
-        let f = File::open(path, OpenOptions::from_bits(0))?;
+        let f = FileState::open(path, OpenOptions::from_bits(0))?;
         let ri = f.open_diriter()?;
         Ok(Self {
             ri,
@@ -76,7 +76,7 @@ pub extern "C" fn mkdir(path: *const c_char, mode: u32) -> c_int {
         set_error(ErrorStatus::InvalidPath);
         return -1;
     };
-    try_errno!(File::open(path, OpenOptions::CREATE_DIRECTORY), -1);
+    try_errno!(FileState::open(path, OpenOptions::CREATE_DIRECTORY), -1);
     0
 }
 #[unsafe(no_mangle)]
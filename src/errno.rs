@@ -22,6 +22,14 @@ pub fn set_error(status: ErrorStatus) {
     }
 }
 
+/// Returns the error status errno currently holds, [`ErrorStatus::Generic`] if errno is 0.
+pub fn get_error() -> ErrorStatus {
+    match unsafe { *errno.0.get() } {
+        0 => ErrorStatus::Generic,
+        e => ErrorStatus::from_u16(e as u16),
+    }
+}
+
 /// Similar to `?` syntax but on error also sets errno and returns a given error value.
 #[macro_export]
 macro_rules! try_errno {
@@ -19,6 +19,8 @@ pub extern "C" fn exit(code: c_int) -> ! {
     }
 
     drop(destructors);
+    crate::stdio::close_streams();
+    crate::file::remove_temp_files();
     _exit(code)
 }
 
@@ -27,8 +29,10 @@ pub extern "C" fn _exit(code: c_int) -> ! {
     syscalls::process::exit(code as isize as usize)
 }
 
+/// Terminates the process abnormally, unlike [`exit`] neither atexit handlers are called
+/// nor streams are flushed and temporary files are left behind.
 #[unsafe(no_mangle)]
-// FIXME: more correct impl.
+// FIXME: more correct impl, should raise `SIGABRT` once signals are supported.
 pub extern "C" fn abort() -> ! {
-    exit(-1)
+    _exit(-1)
 }
@@ -0,0 +1,105 @@
+//! Opening file descriptors and controlling them, see `include/fcntl.h`.
+
+use core::ffi::{CStr, c_char, c_int};
+
+use safa_api::{abi::fs::OpenOptions, errors::ErrorStatus, syscalls::resources};
+
+use crate::{errno::set_error, file::FileUnbuffered, try_errno, unistd};
+
+pub const O_RDONLY: c_int = 0;
+pub const O_WRONLY: c_int = 1;
+pub const O_RDWR: c_int = 2;
+pub const O_ACCMODE: c_int = 3;
+
+pub const O_CREAT: c_int = 0o100;
+pub const O_EXCL: c_int = 0o200;
+pub const O_TRUNC: c_int = 0o1000;
+pub const O_APPEND: c_int = 0o2000;
+pub const O_NONBLOCK: c_int = 0o4000;
+pub const O_CLOEXEC: c_int = 0o2000000;
+
+/// The file status flags that can be changed with `F_SETFL`
+const SETTABLE_FLAGS: c_int = O_APPEND | O_NONBLOCK;
+
+pub const F_DUPFD: c_int = 0;
+pub const F_GETFL: c_int = 3;
+pub const F_SETFL: c_int = 4;
+
+/// Opens `path` with the access mode and flags in `flags`, returns the lowest free descriptor.
+///
+/// The permissions given after `flags` when creating a file are ignored.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn open(path: *const c_char, flags: c_int, _args: ...) -> c_int {
+    let path = unsafe { CStr::from_ptr(path) };
+    let path = try_errno!(path.to_str().map_err(|_| ErrorStatus::InvalidStr), -1);
+
+    let mut options = match flags & O_ACCMODE {
+        O_RDONLY => OpenOptions::READ,
+        O_WRONLY => OpenOptions::WRITE,
+        O_RDWR => OpenOptions::READ | OpenOptions::WRITE,
+        _ => {
+            set_error(ErrorStatus::InvalidArgument);
+            return -1;
+        }
+    };
+
+    if flags & O_CREAT != 0 {
+        options = options | OpenOptions::CREATE_FILE;
+    }
+
+    let file = if flags & (O_CREAT | O_EXCL) == O_CREAT | O_EXCL {
+        FileUnbuffered::open_new(path, options)
+    } else {
+        FileUnbuffered::open(path, options)
+    };
+
+    let mut file = try_errno!(file, -1);
+    if flags & O_TRUNC != 0
+        && flags & O_ACCMODE != O_RDONLY
+        && let Err(e) = file.truncate(0)
+    {
+        _ = resources::destroy(file.resource());
+        set_error(e);
+        return -1;
+    }
+
+    if let Err(e) = file.set_append(flags & O_APPEND != 0) {
+        _ = resources::destroy(file.resource());
+        set_error(e);
+        return -1;
+    }
+    // FIXME: `O_CLOEXEC` is accepted but has no effect, like the `e` mode of `fopen`
+    try_errno!(
+        unistd::install(file, flags & (O_ACCMODE | SETTABLE_FLAGS)),
+        -1
+    )
+}
+
+/// Supports `F_DUPFD`, `F_GETFL` and `F_SETFL`, which take an `int` argument after `cmd` if any.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn fcntl(fd: c_int, cmd: c_int, mut args: ...) -> c_int {
+    let desc = try_errno!(unistd::get(fd), -1);
+
+    match cmd {
+        F_DUPFD => {
+            let min = unsafe { args.arg::<c_int>() };
+            let Ok(min) = usize::try_from(min) else {
+                set_error(ErrorStatus::InvalidArgument);
+                return -1;
+            };
+            try_errno!(unistd::install_from(desc, min), -1)
+        }
+        F_GETFL => desc.lock().flags,
+        F_SETFL => {
+            let flags = unsafe { args.arg::<c_int>() };
+            let mut desc = desc.lock();
+            try_errno!(desc.file.set_append(flags & O_APPEND != 0), -1);
+            desc.flags = (desc.flags & !SETTABLE_FLAGS) | (flags & SETTABLE_FLAGS);
+            0
+        }
+        _ => {
+            set_error(ErrorStatus::InvalidArgument);
+            -1
+        }
+    }
+}
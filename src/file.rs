@@ -1,20 +1,41 @@
-use core::{fmt::Write, mem::MaybeUninit};
+use core::{
+    cell::UnsafeCell,
+    ffi::{CStr, c_int},
+    fmt::{Debug, Write},
+    ops::{Deref, DerefMut, Range},
+};
 
-use alloc::{boxed::Box, format, vec::Vec};
+use alloc::{boxed::Box, format, string::String, vec::Vec};
+use rand_pcg::{
+    Pcg32,
+    rand_core::{Rng, SeedableRng},
+};
 use safa_api::{
     abi::fs::{FSObjectType, OpenOptions},
     errors::ErrorStatus,
+    sync::locks::Mutex,
     syscalls::{self, fs, io, resources, types::Ri},
 };
 
 use crate::{
     dirent::{self, Dir},
     errno::set_error,
+    fcntl::O_RDWR,
     format::CWriter,
     parse::CReader,
+    pthread::RecursiveLock,
+    stdlib::getenv,
+    unistd::{self, Description, DescriptionFile},
 };
 
 const INITIAL_BUFFERING_LEN: usize = 1024;
+/// The directory temporary files are created in if `TMPDIR` isn't set.
+pub const DEFAULT_TEMP_DIR: &str = "ram:/tmp";
+/// The amount of names tried by [`create_temp`] before giving up.
+pub const TEMP_ATTEMPTS: usize = 238328;
+const TEMP_NAME_CHARS: &[u8] = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789";
+/// The amount of bytes that can be pushed back with [`FileState::unread`], C only guarantees one.
+const PUSHBACK_LEN: usize = 8;
 
 #[derive(Debug, Clone, Copy)]
 pub enum BufferingOption {
@@ -34,36 +55,205 @@ impl BufferingOption {
     }
 }
 
+/// The memory a buffered [`File`] reads ahead into and writes to,
+/// either allocated by the file or borrowed from the caller of `setvbuf`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FileBuffer {
+    Owned(Box<[u8]>),
+    /// A caller provided buffer of the given length, it has to outlive its use by the file
+    Borrowed(*mut u8, usize),
+}
+
+// A borrowed buffer is only accessed through the file that borrows it, just like an owned one
+unsafe impl Send for FileBuffer {}
+unsafe impl Sync for FileBuffer {}
+
+impl FileBuffer {
+    pub(crate) fn alloc(size: usize) -> Self {
+        use alloc::vec;
+        Self::Owned(vec![0; size].into_boxed_slice())
+    }
+}
+
+impl Deref for FileBuffer {
+    type Target = [u8];
+    fn deref(&self) -> &Self::Target {
+        match self {
+            Self::Owned(buf) => buf,
+            Self::Borrowed(ptr, len) => unsafe { core::slice::from_raw_parts(*ptr, *len) },
+        }
+    }
+}
+
+impl DerefMut for FileBuffer {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        match self {
+            Self::Owned(buf) => buf,
+            Self::Borrowed(ptr, len) => unsafe { core::slice::from_raw_parts_mut(*ptr, *len) },
+        }
+    }
+}
+
+/// Reads and writes share the same buffer,
+/// pending writes are flushed before reading and bytes read ahead are discarded before writing.
 #[derive(Debug, Clone, PartialEq)]
 pub enum BufferedIO {
+    /// Reads ahead a line at most, writes are flushed at each new line or when the buffer is full
     LineBuffered {
-        stdin_line: Vec<u8>,
-        stdout_line: Vec<u8>,
+        buf: FileBuffer,
+        /// How much of the bytes read ahead was consumed
+        stdin_pos: usize,
+        /// How much of `buf` was filled by reading ahead
+        stdin_len: usize,
+        /// How much of `buf` was written to
+        stdout_pos: usize,
     },
+    /// Reads ahead as much as fits in the buffer, writes are flushed when the buffer is full
     SizeBuffered {
-        stdin: Box<[u8]>,
+        buf: FileBuffer,
+        /// How much of the bytes read ahead was consumed
         stdin_pos: usize,
-        stdout: Box<[u8]>,
+        /// How much of `buf` was filled by reading ahead
+        stdin_len: usize,
+        /// How much of `buf` was written to
         stdout_pos: usize,
     },
     None,
 }
 
 impl BufferedIO {
-    fn flush(&mut self, to: &mut FileUnbuffered) -> Result<usize, ErrorStatus> {
+    /// Returns the amount of bytes read ahead that weren't consumed yet.
+    fn unread_len(&self) -> usize {
+        match self {
+            Self::None => 0,
+            Self::LineBuffered {
+                stdin_pos,
+                stdin_len,
+                ..
+            }
+            | Self::SizeBuffered {
+                stdin_pos,
+                stdin_len,
+                ..
+            } => *stdin_len - *stdin_pos,
+        }
+    }
+
+    /// Returns the amount of bytes written that weren't flushed yet.
+    fn unwritten_len(&self) -> usize {
+        match self {
+            Self::None => 0,
+            Self::LineBuffered { stdout_pos, .. } | Self::SizeBuffered { stdout_pos, .. } => {
+                *stdout_pos
+            }
+        }
+    }
+
+    /// Copies bytes read ahead into `buf`, returns the amount of bytes copied.
+    fn consume(&mut self, into: &mut [u8]) -> usize {
+        match self {
+            Self::None => 0,
+            Self::LineBuffered {
+                buf,
+                stdin_pos,
+                stdin_len,
+                ..
+            }
+            | Self::SizeBuffered {
+                buf,
+                stdin_pos,
+                stdin_len,
+                ..
+            } => {
+                let amount = into.len().min(*stdin_len - *stdin_pos);
+                into[..amount].copy_from_slice(&buf[*stdin_pos..*stdin_pos + amount]);
+                *stdin_pos += amount;
+                amount
+            }
+        }
+    }
+
+    /// Drops the bytes read ahead.
+    fn discard_read(&mut self) {
+        match self {
+            Self::None => {}
+            Self::LineBuffered {
+                stdin_pos,
+                stdin_len,
+                ..
+            }
+            | Self::SizeBuffered {
+                stdin_pos,
+                stdin_len,
+                ..
+            } => {
+                *stdin_pos = 0;
+                *stdin_len = 0;
+            }
+        }
+    }
+
+    /// Buffers `byte` to be written, returns `false` if the buffer is full.
+    fn push(&mut self, byte: u8) -> bool {
+        match self {
+            Self::None => false,
+            Self::LineBuffered {
+                buf, stdout_pos, ..
+            }
+            | Self::SizeBuffered {
+                buf, stdout_pos, ..
+            } => {
+                if *stdout_pos == buf.len() {
+                    return false;
+                }
+                buf[*stdout_pos] = byte;
+                *stdout_pos += 1;
+                true
+            }
+        }
+    }
+
+    /// Returns whether there is no room left for writes.
+    fn is_full(&self) -> bool {
+        match self {
+            Self::None => true,
+            Self::LineBuffered {
+                buf, stdout_pos, ..
+            }
+            | Self::SizeBuffered {
+                buf, stdout_pos, ..
+            } => *stdout_pos == buf.len(),
+        }
+    }
+
+    /// Writes the pending writes to `to` until none is left, returns the amount of bytes written.
+    ///
+    /// If `to` fails or stops accepting bytes, the bytes that weren't written are kept pending.
+    fn flush(&mut self, to: &mut dyn FileBackend) -> Result<usize, ErrorStatus> {
         match self {
             Self::None => Ok(0),
-            Self::LineBuffered { stdout_line, .. } => {
-                let r = to.write_unbuffered(&*stdout_line)?;
-                stdout_line.clear();
-                Ok(r)
+            Self::LineBuffered {
+                buf, stdout_pos, ..
             }
-            Self::SizeBuffered {
-                stdout, stdout_pos, ..
+            | Self::SizeBuffered {
+                buf, stdout_pos, ..
             } => {
-                let r = to.write_unbuffered(&stdout[..*stdout_pos])?;
-                *stdout_pos = 0;
-                Ok(r)
+                let mut written = 0;
+                let results = loop {
+                    if written == *stdout_pos {
+                        break Ok(written);
+                    }
+
+                    match to.write(&buf[written..*stdout_pos]) {
+                        Ok(0) => break Err(ErrorStatus::Generic),
+                        Ok(r) => written += r,
+                        Err(e) => break Err(e),
+                    }
+                };
+
+                buf.copy_within(written..*stdout_pos, 0);
+                *stdout_pos -= written;
+                results
             }
         }
     }
@@ -76,16 +266,95 @@ pub enum SeekPosition {
     Start(usize),
 }
 
-#[derive(Debug, PartialEq)]
-struct FileUnbuffered {
+/// What a [`File`] reads from and writes to without buffering.
+pub(crate) trait FileBackend: Debug + Send + Sync {
+    /// Reads into `buf` at the current position, returns 0 at the end of the file.
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, ErrorStatus>;
+    /// Writes `bytes` at the current position, or at the end of the file when appending.
+    fn write(&mut self, bytes: &[u8]) -> Result<usize, ErrorStatus>;
+    /// Changes the position at which the backend reads and writes.
+    fn seek(&mut self, wrench: SeekPosition) -> Result<(), ErrorStatus>;
+    fn offset(&mut self) -> Result<usize, ErrorStatus>;
+    /// Returns the size of the file in bytes
+    fn size(&mut self) -> Result<usize, ErrorStatus>;
+    /// Makes every write land at the end of the file regardless of the current position.
+    fn set_append(&mut self, append: bool) -> Result<(), ErrorStatus>;
+    /// Makes previous writes visible to others.
+    fn sync(&mut self) -> Result<(), ErrorStatus> {
+        Ok(())
+    }
+    /// Changes the size of the file to `len` bytes.
+    fn truncate(&mut self, len: usize) -> Result<(), ErrorStatus> {
+        _ = len;
+        Err(ErrorStatus::OperationNotSupported)
+    }
+    /// Releases the backend, called once when the file is closed.
+    fn close(&mut self) -> Result<(), ErrorStatus>;
+    /// Returns the `safa_api` resource the backend reads from and writes to if any.
+    fn resource(&self) -> Option<Ri> {
+        None
+    }
+    /// Returns the backend as a [`FileUnbuffered`] if it is one.
+    fn as_unbuffered(&self) -> Option<&FileUnbuffered> {
+        None
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct FileUnbuffered {
     resource: Ri,
     offset: isize,
-    eof: bool,
+    /// Whether every write lands at the end of the file
+    append: bool,
 }
 
 impl FileUnbuffered {
+    pub(crate) const fn new(resource: Ri) -> Self {
+        Self {
+            resource,
+            offset: 0,
+            append: false,
+        }
+    }
+
+    pub(crate) fn open(path: &str, options: OpenOptions) -> Result<Self, ErrorStatus> {
+        Ok(Self::new(fs::open(path, options)?))
+    }
+
+    /// Creates the file `path` and opens it with `options`, like the `x` mode of `fopen`,
+    /// fails with [`ErrorStatus::AlreadyExists`] if anything exists at `path` already.
+    pub(crate) fn open_new(path: &str, options: OpenOptions) -> Result<Self, ErrorStatus> {
+        // Unlike checking whether `path` exists first, nothing can be created at `path` in between
+        fs::create(path)?;
+        Self::open(path, options).inspect_err(|_| {
+            // Nobody could have a handle to the file yet
+            _ = fs::remove_path(path);
+        })
+    }
+
+    pub(crate) const fn resource(&self) -> Ri {
+        self.resource
+    }
+
+    /// Makes every write land at the end of the file regardless of the current position,
+    /// once appending stops the file stays at the position it was at.
+    pub(crate) fn set_append(&mut self, append: bool) -> Result<(), ErrorStatus> {
+        if append {
+            self.offset = -1;
+        } else if self.offset < 0 {
+            // Reads and writes don't move a position relative to the end
+            self.offset = self.offset()? as isize;
+        }
+        self.append = append;
+        Ok(())
+    }
+
     /// Writes `bytes` to file at the current position.
-    fn write_unbuffered(&mut self, bytes: &[u8]) -> Result<usize, ErrorStatus> {
+    pub(crate) fn write_unbuffered(&mut self, bytes: &[u8]) -> Result<usize, ErrorStatus> {
+        if self.append {
+            self.offset = -1;
+        }
+
         let results = io::write(self.resource, self.offset, bytes)?;
         if self.offset >= 0 || self.offset < -1 {
             self.offset += results as isize;
@@ -94,13 +363,11 @@ impl FileUnbuffered {
     }
 
     /// Reads into `buf` from the file at the current position.
-    fn read_unbuffered(&mut self, buf: &mut [u8]) -> Result<usize, ErrorStatus> {
+    pub(crate) fn read_unbuffered(&mut self, buf: &mut [u8]) -> Result<usize, ErrorStatus> {
         let results = io::read(self.resource, self.offset, buf)?;
         if self.offset >= 0 {
             self.offset += results as isize;
         }
-
-        self.eof = results == 0;
         Ok(results)
     }
 
@@ -108,18 +375,18 @@ impl FileUnbuffered {
         syscalls::io::sync(self.resource)
     }
 
-    pub fn offset(&self) -> usize {
+    pub fn offset(&self) -> Result<usize, ErrorStatus> {
         if self.offset >= 0 {
-            self.offset as usize
+            Ok(self.offset as usize)
         } else {
-            let size = self.size();
-            size.saturating_add_signed(self.offset + 1)
+            let size = self.size()?;
+            Ok(size.saturating_add_signed(self.offset + 1))
         }
     }
 
     /// Returns the size of the file in bytes
-    pub fn size(&self) -> usize {
-        syscalls::io::fsize(self.resource).expect("fsize should never fail")
+    pub fn size(&self) -> Result<usize, ErrorStatus> {
+        syscalls::io::fsize(self.resource)
     }
 
     /// Changes the position at which the file reads and writes.
@@ -134,55 +401,224 @@ impl FileUnbuffered {
             }
         }
     }
+
+    pub(crate) fn truncate(&mut self, len: usize) -> Result<(), ErrorStatus> {
+        io::truncate(self.resource, len)
+    }
 }
 
-impl CReader for FileUnbuffered {
-    #[inline(always)]
-    fn read_bytes(&mut self, buf: &mut [u8]) -> Result<usize, ErrorStatus> {
+impl FileBackend for FileUnbuffered {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, ErrorStatus> {
         self.read_unbuffered(buf)
     }
-    fn unread_byte(&mut self, _: u8) {
-        self.seek(SeekPosition::Current(-1));
+
+    fn write(&mut self, bytes: &[u8]) -> Result<usize, ErrorStatus> {
+        self.write_unbuffered(bytes)
+    }
+
+    fn seek(&mut self, wrench: SeekPosition) -> Result<(), ErrorStatus> {
+        FileUnbuffered::seek(self, wrench);
+        Ok(())
+    }
+
+    fn offset(&mut self) -> Result<usize, ErrorStatus> {
+        FileUnbuffered::offset(self)
+    }
+
+    fn size(&mut self) -> Result<usize, ErrorStatus> {
+        FileUnbuffered::size(self)
+    }
+
+    fn set_append(&mut self, append: bool) -> Result<(), ErrorStatus> {
+        FileUnbuffered::set_append(self, append)
+    }
+
+    fn sync(&mut self) -> Result<(), ErrorStatus> {
+        FileUnbuffered::sync(self)
+    }
+
+    fn truncate(&mut self, len: usize) -> Result<(), ErrorStatus> {
+        FileUnbuffered::truncate(self, len)
+    }
+
+    fn close(&mut self) -> Result<(), ErrorStatus> {
+        resources::destroy(self.resource)
+    }
+
+    fn resource(&self) -> Option<Ri> {
+        Some(self.resource)
+    }
+
+    fn as_unbuffered(&self) -> Option<&FileUnbuffered> {
+        Some(self)
     }
 }
 
-#[derive(Debug, PartialEq)]
+/// A stream, `FILE` in C, its state is only accessed by the thread holding its lock, see [`FileGuard`].
+#[derive(Debug)]
 pub struct File {
-    inner: FileUnbuffered,
+    /// Held while the file is in use by a thread
+    lock: RecursiveLock,
+    state: UnsafeCell<FileState>,
+}
+
+// The state is only accessed by the thread holding the lock
+unsafe impl Sync for File {}
+
+impl File {
+    pub const fn new(state: FileState) -> Self {
+        Self {
+            lock: RecursiveLock::new(),
+            state: UnsafeCell::new(state),
+        }
+    }
+
+    /// Returns the state of this file, which needs no locking as the file isn't shared.
+    pub fn get_mut(&mut self) -> &mut FileState {
+        self.state.get_mut()
+    }
+
+    /// Returns a pointer to the state of this file, it may only be dereferenced by the thread holding the lock
+    /// (or if no other thread uses the file), see [`FileGuard`].
+    pub const fn state(&self) -> *mut FileState {
+        self.state.get()
+    }
+
+    /// Locks this file for the current thread, waiting for the thread holding it to unlock it first,
+    /// a thread can lock a file it already holds, it is unlocked once [`Self::unlock`] is called as many times.
+    pub fn lock(&self) {
+        self.lock.lock();
+    }
+
+    /// Locks this file for the current thread without waiting, returns `false` if another thread holds it.
+    pub fn try_lock(&self) -> bool {
+        self.lock.try_lock()
+    }
+
+    /// Unlocks this file once, the current thread must hold it.
+    pub fn unlock(&self) {
+        self.lock.unlock();
+    }
+}
+
+/// What a [`File`] reads from and writes to and how.
+#[derive(Debug)]
+pub struct FileState {
+    inner: Box<dyn FileBackend>,
     buffering: BufferedIO,
     closed: bool,
+    /// The end of file indicator, set when a read reaches the end of the file
+    eof: bool,
+    /// Bytes pushed back by [`FileState::unread`], the last one pushed back is read first
+    pushback: [u8; PUSHBACK_LEN],
+    pushback_len: usize,
+    /// The error indicator, set when reading, writing or flushing fails until cleared by [`Self::clear_error`]
+    error: bool,
+    /// The path of the temporary file removed when this file is closed, see [`Self::remove_on_close`]
+    temp_path: Option<String>,
+    /// The descriptor owning the underlying resource if any, closed instead of the resource
+    fd: Option<c_int>,
 }
 
-impl File {
-    pub fn from_res(res: Ri, option: BufferingOption, from_end: bool) -> Self {
-        let mut this = Self {
-            inner: FileUnbuffered {
-                resource: res,
-                offset: if from_end { -1 } else { 0 },
-                eof: false,
-            },
+impl FileState {
+    /// Creates an unbuffered file reading from and writing to `backend`.
+    pub(crate) fn from_backend(backend: Box<dyn FileBackend>) -> Self {
+        Self {
+            inner: backend,
             buffering: BufferedIO::None,
             closed: false,
-        };
+            eof: false,
+            pushback: [0; PUSHBACK_LEN],
+            pushback_len: 0,
+            error: false,
+            temp_path: None,
+            fd: None,
+        }
+    }
+
+    pub fn from_res(res: Ri, option: BufferingOption, from_end: bool) -> Self {
+        let mut inner = FileUnbuffered::new(res);
+        if from_end {
+            inner.seek(SeekPosition::End(0));
+        }
+
+        let mut this = Self::from_backend(Box::new(inner));
         this.set_buffering(option, 0);
         this
     }
 
     pub fn open_diriter(&self) -> Result<Ri, ErrorStatus> {
-        syscalls::io::diriter_open(self.inner.resource)
+        let ri = self.res().ok_or(ErrorStatus::NotADirectory)?;
+        syscalls::io::diriter_open(ri)
     }
 
     pub fn open(path: &str, options: OpenOptions) -> Result<Self, ErrorStatus> {
-        let ri = fs::open(path, options)?;
-        Ok(Self {
-            inner: FileUnbuffered {
-                resource: ri,
-                offset: 0,
-                eof: false,
-            },
-            buffering: BufferedIO::None,
-            closed: false,
-        })
+        let inner = FileUnbuffered::open(path, options)?;
+        Ok(Self::from_backend(Box::new(inner)))
+    }
+
+    /// Creates the file `path` and opens it with `options`, like the `x` mode of `fopen`,
+    /// fails with [`ErrorStatus::AlreadyExists`] if anything exists at `path` already.
+    pub fn open_new(path: &str, options: OpenOptions) -> Result<Self, ErrorStatus> {
+        let inner = FileUnbuffered::open_new(path, options)?;
+        Ok(Self::from_backend(Box::new(inner)))
+    }
+
+    /// Makes every write land at the end of the file regardless of the current position.
+    pub fn set_append(&mut self, append: bool) -> Result<(), ErrorStatus> {
+        self.inner.set_append(append)
+    }
+
+    /// Returns the underlying resource, `None` if the file isn't backed by a resource.
+    pub fn res(&self) -> Option<Ri> {
+        self.inner.resource()
+    }
+
+    pub const fn fd(&self) -> Option<c_int> {
+        self.fd
+    }
+
+    /// Makes `fd` the descriptor of this file, which reads from and writes to its open file,
+    /// closing this file closes `fd` as well.
+    pub(crate) fn from_descriptor(fd: c_int, desc: Description) -> Self {
+        let mut this = Self::from_backend(Box::new(DescriptionFile::new(desc)));
+        this.fd = Some(fd);
+        this
+    }
+
+    /// Returns the descriptor of this file, giving the underlying resource one first if there is none,
+    /// the descriptor then shares its position with this file and is closed with it.
+    ///
+    /// Fails with [`ErrorStatus::UnknownResource`] (`EBADF`) if the file isn't backed by a resource.
+    pub fn fileno(&mut self) -> Result<c_int, ErrorStatus> {
+        if let Some(fd) = self.fd {
+            return Ok(fd);
+        }
+
+        let file = self
+            .inner
+            .as_unbuffered()
+            .ok_or(ErrorStatus::UnknownResource)?;
+        let desc = unistd::describe(file.clone(), O_RDWR);
+        let fd = unistd::install_from(desc.clone(), 0)?;
+        // The open file owns the resource from now on
+        self.inner = Box::new(DescriptionFile::new(desc));
+        self.fd = Some(fd);
+        Ok(fd)
+    }
+
+    /// Changes the size of the file to `len` bytes, pending writes are flushed first.
+    pub fn truncate(&mut self, len: usize) -> Result<(), ErrorStatus> {
+        self.flush()?;
+        self.discard_read_ahead();
+        self.inner.truncate(len)
+    }
+
+    /// Makes this file a temporary file removed when it is closed or when the process exits,
+    /// `path` is the path the file was opened at.
+    pub fn remove_on_close(&mut self, path: String) {
+        TEMP_FILES.lock().push(path.clone());
+        self.temp_path = Some(path);
     }
 
     pub fn close(mut self) -> Result<(), ErrorStatus> {
@@ -193,7 +629,16 @@ impl File {
         if !self.closed {
             self.closed = true;
             _ = self.flush();
-            resources::destroy(self.inner.resource)
+            let mut results = self.inner.close();
+            if let Some(fd) = self.fd {
+                results = unistd::close_fd(fd).and(results);
+            }
+
+            if let Some(path) = self.temp_path.take() {
+                TEMP_FILES.lock().retain(|p| *p != path);
+                _ = syscalls::fs::remove_path(&path);
+            }
+            results
         } else {
             Ok(())
         }
@@ -202,108 +647,192 @@ impl File {
     /// Writes `bytes` to file,
     ///
     /// writes may be buffer until a call to [`Self::flush`].
+    /// Writes `bytes`, returns the amount of bytes written which is never 0 unless `bytes` is empty.
     pub fn write(&mut self, bytes: &[u8]) -> Result<usize, ErrorStatus> {
-        let mut len = 0;
+        let results = match self.write_buffered(bytes) {
+            // The backend accepted nothing, writing again would make no progress either
+            Ok(0) if !bytes.is_empty() => Err(ErrorStatus::Generic),
+            results => results,
+        };
+        self.error |= results.is_err();
+        results
+    }
 
-        match self.buffering {
-            BufferedIO::LineBuffered {
-                stdout_line: ref mut buf,
-                ..
-            } => {
-                for c in bytes {
-                    len += 1;
-                    buf.push(*c);
+    fn write_buffered(&mut self, bytes: &[u8]) -> Result<usize, ErrorStatus> {
+        self.discard_read_ahead();
+        let line_buffered = match self.buffering {
+            BufferedIO::None => return self.inner.write(bytes),
+            BufferedIO::LineBuffered { .. } => true,
+            BufferedIO::SizeBuffered { .. } => false,
+        };
 
-                    if *c == b'\n' {
-                        self.inner.write_unbuffered(&*buf)?;
-                        buf.clear();
-                    }
+        let mut len = 0;
+        while len < bytes.len() {
+            let c = bytes[len];
+            // The buffer may still be full if flushing it failed before
+            if self.buffering.push(c) {
+                len += 1;
+                let line_ended = line_buffered && c == b'\n';
+                if !(self.buffering.is_full() || line_ended) {
+                    continue;
                 }
             }
 
-            BufferedIO::SizeBuffered {
-                stdout: ref mut buf,
-                ref mut stdout_pos,
-                ..
-            } => {
-                for c in bytes {
-                    len += 1;
-                    buf[*stdout_pos] = *c;
-                    *stdout_pos += 1;
-
-                    if *stdout_pos >= buf.len() {
-                        self.inner.write_unbuffered(&*buf)?;
-                        *stdout_pos = 0;
-                    }
+            if let Err(e) = self.buffering.flush(&mut *self.inner) {
+                // The bytes buffered so far are kept, the error is reported by the next write
+                if len != 0 {
+                    self.error = true;
+                    return Ok(len);
                 }
+                return Err(e);
             }
-            BufferedIO::None => return self.inner.write_unbuffered(bytes),
         }
 
         Ok(len)
     }
 
-    /// Reads `bytes` from file,
+    /// Reads `bytes` from file, starting with the bytes pushed back by [`Self::unread`],
     ///
     /// read may buffer, reading more than requested.
     pub fn read(&mut self, buf: &mut [u8]) -> Result<usize, ErrorStatus> {
+        let mut unread = 0;
+        while unread < buf.len() && self.pushback_len > 0 {
+            self.pushback_len -= 1;
+            buf[unread] = self.pushback[self.pushback_len];
+            unread += 1;
+        }
+
+        if unread == buf.len() {
+            return Ok(unread);
+        }
+
+        match self.read_buffered(&mut buf[unread..]) {
+            Ok(read) => {
+                self.eof |= unread + read == 0;
+                Ok(unread + read)
+            }
+            Err(e) => {
+                self.error = true;
+                // The error is reported by the next read
+                if unread != 0 { Ok(unread) } else { Err(e) }
+            }
+        }
+    }
+
+    /// Pushes `byte` back so that it is the next byte read and clears the end of file indicator,
+    /// returns `false` if there is no room left to push back.
+    pub fn unread(&mut self, byte: u8) -> bool {
+        if self.pushback_len == PUSHBACK_LEN {
+            return false;
+        }
+
+        self.pushback[self.pushback_len] = byte;
+        self.pushback_len += 1;
+        self.eof = false;
+        true
+    }
+
+    fn read_buffered(&mut self, buf: &mut [u8]) -> Result<usize, ErrorStatus> {
+        // Pending writes have to land before anything is read
+        if self.buffering.unwritten_len() != 0 {
+            self.buffering.flush(&mut *self.inner)?;
+        }
+
+        let consumed = self.buffering.consume(buf);
+        if consumed != 0 || buf.is_empty() {
+            return Ok(consumed);
+        }
+
         match &mut self.buffering {
-            BufferedIO::None => return self.inner.read_unbuffered(buf),
+            BufferedIO::None => return self.inner.read(buf),
             BufferedIO::LineBuffered {
-                stdin_line: line, ..
+                buf: line,
+                stdin_pos,
+                stdin_len,
+                ..
             } => {
-                if line.len() == 0 {
-                    let am = self
-                        .inner
-                        .read_bytes_until_or_eof_alloc(line, usize::MAX, |c| c == b'\n')?;
-                    if am == 0 {
-                        return Ok(0);
+                *stdin_pos = 0;
+                *stdin_len = 0;
+                // Never read past the end of the line
+                while *stdin_len < line.len() {
+                    let byte = &mut line[*stdin_len..*stdin_len + 1];
+                    if self.inner.read(byte)? == 0 {
+                        break;
                     }
 
-                    return self.read(buf);
-                } else {
-                    let amount = buf.len().min(line.len());
-                    buf[..amount].copy_from_slice(&line[..amount]);
-                    return Ok(amount);
+                    *stdin_len += 1;
+                    if byte[0] == b'\n' {
+                        break;
+                    }
                 }
             }
             BufferedIO::SizeBuffered {
-                stdin, stdin_pos, ..
+                buf: ahead,
+                stdin_pos,
+                stdin_len,
+                ..
             } => {
-                if *stdin_pos != 0 {
-                    let amount = buf.len().min(*stdin_pos);
-                    buf[..amount].copy_from_slice(&stdin[..amount]);
-                    Ok(amount)
-                } else {
-                    let unfilled = &mut stdin[*stdin_pos..];
-                    let am = self.inner.read_unbuffered(unfilled)?;
-                    if am == 0 {
-                        return Ok(0);
-                    }
-
-                    return self.read(buf);
+                // Reads that wouldn't fit in the buffer bypass it
+                if buf.len() >= ahead.len() {
+                    return self.inner.read(buf);
                 }
+
+                *stdin_pos = 0;
+                *stdin_len = 0;
+                *stdin_len = self.inner.read(ahead)?;
             }
         }
+
+        Ok(self.buffering.consume(buf))
     }
 
-    /// Changes the position at which the file reads and writes.
-    pub fn seek(&mut self, wrench: SeekPosition) {
-        self.inner.seek(wrench)
+    /// Drops the bytes read ahead and moves back to the position the reader is at.
+    fn discard_read_ahead(&mut self) {
+        let unread = self.buffering.unread_len();
+        if unread != 0 {
+            _ = self.inner.seek(SeekPosition::Current(-(unread as isize)));
+        }
+        self.buffering.discard_read();
+    }
+
+    /// Changes the position at which the file reads and writes and clears the end of file indicator,
+    /// pending writes are flushed first and bytes read ahead or pushed back are discarded.
+    pub fn seek(&mut self, wrench: SeekPosition) -> Result<(), ErrorStatus> {
+        if let Err(e) = self.buffering.flush(&mut *self.inner) {
+            self.error = true;
+            return Err(e);
+        }
+
+        // The reader is behind the underlying file by the amount of bytes read ahead or pushed back
+        let wrench = match wrench {
+            SeekPosition::Current(c) => SeekPosition::Current(
+                c.saturating_sub_unsigned(self.buffering.unread_len() + self.pushback_len),
+            ),
+            wrench => wrench,
+        };
+
+        self.buffering.discard_read();
+        self.pushback_len = 0;
+        self.inner.seek(wrench)?;
+        self.eof = false;
+        Ok(())
     }
 
     /// Returns the size of the file in bytes
-    pub fn size(&self) -> usize {
+    pub fn size(&mut self) -> Result<usize, ErrorStatus> {
         self.inner.size()
     }
 
-    pub fn offset(&self) -> usize {
-        self.inner.offset()
+    pub fn offset(&mut self) -> Result<usize, ErrorStatus> {
+        // Bytes read ahead or pushed back are not read yet, bytes waiting to be written are already
+        Ok((self.inner.offset()? + self.buffering.unwritten_len())
+            .saturating_sub(self.buffering.unread_len() + self.pushback_len))
     }
 
     pub fn flush(&mut self) -> Result<(), ErrorStatus> {
-        let r = self.buffering.flush(&mut self.inner);
+        let r = self.buffering.flush(&mut *self.inner);
         let o_r = self.inner.sync();
+        self.error |= r.is_err() || o_r.is_err();
 
         if r.is_err() {
             return r.map(|_| ());
@@ -313,34 +842,54 @@ impl File {
     }
 
     pub fn is_eof(&self) -> bool {
-        self.inner.eof
+        self.eof
     }
 
-    /// Changes how this file is buffered.
+    pub fn is_error(&self) -> bool {
+        self.error
+    }
+
+    /// Clears both the end of file and the error indicators.
+    pub fn clear_error(&mut self) {
+        self.eof = false;
+        self.error = false;
+    }
+
+    /// Changes how this file is buffered, allocating a buffer of at least `size` bytes.
     pub fn set_buffering(&mut self, option: BufferingOption, size: usize) {
-        use alloc::vec;
+        let size = size.max(INITIAL_BUFFERING_LEN);
+        self.set_buffer(option, FileBuffer::alloc(size));
+    }
 
+    /// Changes how this file is buffered, using the `size` bytes at `buffer` as the buffer.
+    ///
+    /// # Safety
+    /// `buffer` must be valid for reads and writes of `size` bytes until the file is closed or its buffering changes.
+    pub unsafe fn set_buffering_borrowed(
+        &mut self,
+        option: BufferingOption,
+        buffer: *mut u8,
+        size: usize,
+    ) {
+        self.set_buffer(option, FileBuffer::Borrowed(buffer, size));
+    }
+
+    fn set_buffer(&mut self, option: BufferingOption, buf: FileBuffer) {
         _ = self.flush();
-        let size = size.max(INITIAL_BUFFERING_LEN);
+        self.discard_read_ahead();
 
         let io = match option {
             BufferingOption::None => BufferedIO::None,
             BufferingOption::LineBuffered => BufferedIO::LineBuffered {
-                stdin_line: Vec::with_capacity(size),
-                stdout_line: Vec::with_capacity(size),
+                buf,
+                stdin_pos: 0,
+                stdin_len: 0,
+                stdout_pos: 0,
             },
             BufferingOption::Buffered => BufferedIO::SizeBuffered {
-                stdin: unsafe {
-                    vec![MaybeUninit::uninit(); size]
-                        .into_boxed_slice()
-                        .assume_init()
-                },
+                buf,
                 stdin_pos: 0,
-                stdout: unsafe {
-                    vec![MaybeUninit::uninit(); size]
-                        .into_boxed_slice()
-                        .assume_init()
-                },
+                stdin_len: 0,
                 stdout_pos: 0,
             },
         };
@@ -349,23 +898,65 @@ impl File {
     }
 }
 
-impl CReader for File {
+/// A [`File`] locked by the current thread until the guard is dropped, dereferences to the state of the file.
+pub struct FileGuard(*const File);
+
+impl FileGuard {
+    /// Locks the file at `file`, waiting for the thread holding it to unlock it first.
+    ///
+    /// # Safety
+    /// `file` must point to a valid file that outlives the guard.
+    pub unsafe fn lock(file: *mut File) -> Self {
+        unsafe { (*file).lock() };
+        Self(file)
+    }
+
+    /// Locks the file at `file` without waiting, returns `None` if another thread holds it.
+    ///
+    /// # Safety
+    /// `file` must point to a valid file that outlives the guard.
+    pub unsafe fn try_lock(file: *mut File) -> Option<Self> {
+        unsafe { (*file).try_lock() }.then_some(Self(file))
+    }
+}
+
+impl Deref for FileGuard {
+    type Target = FileState;
+    fn deref(&self) -> &Self::Target {
+        unsafe { &*(*self.0).state() }
+    }
+}
+
+impl DerefMut for FileGuard {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        unsafe { &mut *(*self.0).state() }
+    }
+}
+
+impl Drop for FileGuard {
+    fn drop(&mut self) {
+        unsafe { (*self.0).unlock() };
+    }
+}
+
+impl CReader for FileState {
+    type Error = ErrorStatus;
     fn read_bytes(&mut self, buf: &mut [u8]) -> Result<usize, ErrorStatus> {
         self.read(buf)
     }
 
-    fn unread_byte(&mut self, _b: u8) {
-        self.seek(SeekPosition::Current(-1));
+    fn unread_byte(&mut self, b: u8) {
+        self.unread(b);
     }
 }
 
-impl Drop for File {
+impl Drop for FileState {
     fn drop(&mut self) {
         unsafe { self.close_ref().expect("Failed to close File") }
     }
 }
 
-impl Write for File {
+impl Write for FileState {
     fn write_str(&mut self, s: &str) -> core::fmt::Result {
         self.write(s.as_bytes())
             .map_err(|e| {
@@ -376,15 +967,16 @@ impl Write for File {
     }
 }
 
-impl CWriter for File {
+impl CWriter for FileState {
+    type Error = ErrorStatus;
     fn write_bytes(&mut self, bytes: &[u8]) -> Result<usize, ErrorStatus> {
         self.write(bytes)
     }
 }
 
 fn copy(from: &str, to: &str) -> Result<u64, ErrorStatus> {
-    let mut reader = File::open(from, OpenOptions::READ)?;
-    let mut writer = File::open(to, OpenOptions::WRITE | OpenOptions::CREATE_FILE)?;
+    let mut reader = FileState::open(from, OpenOptions::READ)?;
+    let mut writer = FileState::open(to, OpenOptions::WRITE | OpenOptions::CREATE_FILE)?;
 
     let mut buf = [0u8; 1024];
     let mut total = 0;
@@ -434,3 +1026,99 @@ pub fn rename(old: &str, new: &str) -> Result<(), ErrorStatus> {
         _ => Err(ErrorStatus::NotSupported),
     }
 }
+
+/// The paths of the temporary files that are still open, see [`FileState::remove_on_close`]
+static TEMP_FILES: Mutex<Vec<String>> = Mutex::new(Vec::new());
+static TEMP_RNG: Mutex<Option<Pcg32>> = Mutex::new(None);
+
+/// Removes the temporary files that weren't closed, called at exit.
+pub fn remove_temp_files() {
+    for path in TEMP_FILES.lock().drain(..) {
+        _ = syscalls::fs::remove_path(&path);
+    }
+}
+
+/// Returns the directory temporary files are created in, that is `TMPDIR` or [`DEFAULT_TEMP_DIR`].
+pub fn temp_dir() -> String {
+    let tmpdir = unsafe { getenv(c"TMPDIR".as_ptr()) };
+    if !tmpdir.is_null()
+        && let Ok(dir) = unsafe { CStr::from_ptr(tmpdir) }.to_str()
+        && !dir.is_empty()
+    {
+        return dir.trim_end_matches('/').into();
+    }
+
+    // The default directory may not exist yet
+    _ = syscalls::fs::createdir(DEFAULT_TEMP_DIR);
+    DEFAULT_TEMP_DIR.into()
+}
+
+/// Returns a seed for [`TEMP_RNG`] that differs between processes and runs, so that temporary names
+/// can't be guessed, made of the hardware random number generator if any, the time and the load addresses.
+fn temp_seed() -> u64 {
+    let local = 0u8;
+    let mut seed = syscalls::misc::uptime();
+    seed ^= (&raw const local as u64).rotate_left(32);
+    seed ^= (temp_seed as usize as u64).rotate_left(48);
+
+    #[cfg(target_arch = "x86_64")]
+    {
+        use core::arch::x86_64::{__cpuid, _rdrand64_step, _rdtsc};
+
+        /// Returns a value from `rdrand`, `None` if it failed to gather enough entropy.
+        #[target_feature(enable = "rdrand")]
+        unsafe fn rdrand() -> Option<u64> {
+            let mut value = 0;
+            (unsafe { _rdrand64_step(&mut value) } == 1).then_some(value)
+        }
+
+        seed ^= unsafe { _rdtsc() };
+        // `rdrand` is supported if bit 30 of ECX is set for leaf 1
+        let supported = unsafe { __cpuid(1) }.ecx & (1 << 30) != 0;
+        if supported && let Some(value) = unsafe { rdrand() } {
+            seed ^= value;
+        }
+    }
+    seed
+}
+
+/// Fills `template[random]` with random characters and calls `create` with the resulting path
+/// until it doesn't fail with [`ErrorStatus::AlreadyExists`], giving up after [`TEMP_ATTEMPTS`] tries.
+///
+/// `create` must fail with [`ErrorStatus::AlreadyExists`] without touching what exists at the path if anything does,
+/// `template` is left containing the last path tried.
+pub fn create_temp<T>(
+    template: &mut [u8],
+    random: Range<usize>,
+    mut create: impl FnMut(&str) -> Result<T, ErrorStatus>,
+) -> Result<T, ErrorStatus> {
+    for _ in 0..TEMP_ATTEMPTS {
+        {
+            let mut rng = TEMP_RNG.lock();
+            let rng = rng.get_or_insert_with(|| Pcg32::seed_from_u64(temp_seed()));
+            for c in &mut template[random.clone()] {
+                *c = TEMP_NAME_CHARS[rng.next_u32() as usize % TEMP_NAME_CHARS.len()];
+            }
+        }
+
+        let path = str::from_utf8(template).map_err(|_| ErrorStatus::InvalidStr)?;
+        match create(path) {
+            Err(ErrorStatus::AlreadyExists) => continue,
+            results => return results,
+        }
+    }
+
+    Err(ErrorStatus::AlreadyExists)
+}
+
+/// Creates a new file at a path made from `template` as [`create_temp`] does, opened for reading and writing,
+/// a file that already exists is never opened.
+pub(crate) fn create_temp_file(
+    template: &mut [u8],
+    random: Range<usize>,
+) -> Result<FileUnbuffered, ErrorStatus> {
+    let options = OpenOptions::READ | OpenOptions::WRITE | OpenOptions::CREATE_FILE;
+    create_temp(template, random, |path| {
+        FileUnbuffered::open_new(path, options)
+    })
+}
@@ -3,13 +3,15 @@
 #![feature(thread_local)]
 #![feature(trim_prefix_suffix)]
 
+pub mod cookie;
 pub mod dirent;
 pub mod errno;
 pub mod exit;
+pub mod fcntl;
 pub mod file;
-pub mod format;
 pub mod math;
-pub mod parse;
+pub mod memstream;
+pub mod printf;
 pub mod pthread;
 pub mod setjmp;
 pub mod signal;
@@ -19,6 +21,9 @@ pub mod stdlib;
 pub mod string;
 pub mod strings;
 pub mod time;
+pub mod unistd;
+
+pub use salibc_fmt::{args, format, parse};
 
 pub extern crate alloc;
 
@@ -31,8 +36,8 @@ use safa_api::ffi::{slice::Slice, str::Str};
 use safa_api::process::stdio::{systry_get_stderr, systry_get_stdin, systry_get_stdout};
 
 use crate::exit::exit;
-use crate::file::File;
-use crate::stdio::{STDERR_RAW, STDIN_RAW, STDOUT_RAW, stderr, stdin, stdout};
+use crate::file::{File, FileState};
+use crate::stdio::{STDERR_RAW, STDIN_RAW, STDOUT_RAW, register_stream, stderr, stdin, stdout};
 
 unsafe extern "C" {
     fn main(argc: i32, argv: *const *mut c_char) -> i32;
@@ -41,31 +46,28 @@ unsafe extern "C" {
 extern "C" fn _libc_init(argc: i32, argv: *const *const u8) -> i32 {
     if let Some(ri) = systry_get_stdout().into() {
         unsafe {
-            let r = (*STDOUT_RAW.get()).write(File::from_res(
-                ri,
-                file::BufferingOption::LineBuffered,
-                false,
-            ));
-            *stdout.0.get() = r;
+            let mut f = FileState::from_descriptor(1, unistd::install_std(1, ri));
+            f.set_buffering(file::BufferingOption::LineBuffered, 0);
+            let r = (*STDOUT_RAW.get()).write(File::new(f));
+            *stdout.0.get() = register_stream(r);
         }
     };
 
     if let Some(ri) = systry_get_stderr().into() {
         unsafe {
-            let r =
-                (*STDERR_RAW.get()).write(File::from_res(ri, file::BufferingOption::None, false));
-            *stderr.0.get() = r;
+            let mut f = FileState::from_descriptor(2, unistd::install_std(2, ri));
+            f.set_buffering(file::BufferingOption::None, 0);
+            let r = (*STDERR_RAW.get()).write(File::new(f));
+            *stderr.0.get() = register_stream(r);
         }
     };
 
     if let Some(ri) = systry_get_stdin().into() {
         unsafe {
-            let r = (*STDIN_RAW.get()).write(File::from_res(
-                ri,
-                file::BufferingOption::Buffered,
-                false,
-            ));
-            *stdin.0.get() = r;
+            let mut f = FileState::from_descriptor(0, unistd::install_std(0, ri));
+            f.set_buffering(file::BufferingOption::Buffered, 0);
+            let r = (*STDIN_RAW.get()).write(File::new(f));
+            *stdin.0.get() = register_stream(r);
         }
     };
     return unsafe { main(argc, argv.cast()) };
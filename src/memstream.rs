@@ -0,0 +1,264 @@
+//! [`File`](crate::file::File) backends over memory, see `fmemopen` and `open_memstream` in `include/stdio.h`.
+
+use core::{
+    ffi::{c_char, c_void},
+    ptr::null_mut,
+};
+
+use safa_api::errors::ErrorStatus;
+
+use crate::{
+    file::{FileBackend, FileBuffer, SeekPosition},
+    stdlib::{free, malloc},
+};
+
+/// Returns the position `wrench` moves to from `pos` in contents of `len` bytes,
+/// fails with [`ErrorStatus::InvalidArgument`] if the position would be negative.
+fn seek_in(pos: usize, len: usize, wrench: SeekPosition) -> Result<usize, ErrorStatus> {
+    match wrench {
+        SeekPosition::Start(s) => Some(s),
+        SeekPosition::End(e) => len.checked_sub(e),
+        SeekPosition::Current(c) => pos.checked_add_signed(c),
+    }
+    .ok_or(ErrorStatus::InvalidArgument)
+}
+
+/// A stream over a buffer of a fixed size, see `fmemopen`.
+#[derive(Debug)]
+pub(crate) struct MemFile {
+    buf: FileBuffer,
+    /// The size of the contents, reads stop there
+    len: usize,
+    pos: usize,
+    append: bool,
+    readable: bool,
+    writable: bool,
+}
+
+impl MemFile {
+    /// Creates a stream over `buf`,
+    /// the contents are emptied if `truncate` and otherwise end at the first NUL when appending or at the end of `buf`.
+    pub(crate) fn new(
+        mut buf: FileBuffer,
+        readable: bool,
+        writable: bool,
+        truncate: bool,
+        append: bool,
+    ) -> Self {
+        let len = if truncate {
+            if let Some(first) = buf.first_mut() {
+                *first = 0;
+            }
+            0
+        } else if append {
+            buf.iter().position(|c| *c == 0).unwrap_or(buf.len())
+        } else {
+            buf.len()
+        };
+
+        Self {
+            buf,
+            len,
+            pos: if append { len } else { 0 },
+            append,
+            readable,
+            writable,
+        }
+    }
+}
+
+impl FileBackend for MemFile {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, ErrorStatus> {
+        if !self.readable {
+            return Err(ErrorStatus::UnknownResource);
+        }
+
+        let ahead = self.buf.get(self.pos..self.len).unwrap_or_default();
+        let amount = buf.len().min(ahead.len());
+        buf[..amount].copy_from_slice(&ahead[..amount]);
+        self.pos += amount;
+        Ok(amount)
+    }
+
+    fn write(&mut self, bytes: &[u8]) -> Result<usize, ErrorStatus> {
+        if !self.writable {
+            return Err(ErrorStatus::UnknownResource);
+        }
+
+        if self.append {
+            self.pos = self.len;
+        }
+
+        let room = &mut self.buf[self.pos..];
+        let amount = bytes.len().min(room.len());
+        // No space is left
+        if amount == 0 && !bytes.is_empty() {
+            return Err(ErrorStatus::InvalidSize);
+        }
+
+        room[..amount].copy_from_slice(&bytes[..amount]);
+        self.pos += amount;
+        self.len = self.len.max(self.pos);
+
+        // The contents are kept NUL terminated while there is room for it
+        if let Some(end) = self.buf.get_mut(self.len) {
+            *end = 0;
+        }
+        Ok(amount)
+    }
+
+    fn seek(&mut self, wrench: SeekPosition) -> Result<(), ErrorStatus> {
+        let pos = seek_in(self.pos, self.len, wrench)?;
+        if pos > self.buf.len() {
+            return Err(ErrorStatus::InvalidArgument);
+        }
+
+        self.pos = pos;
+        Ok(())
+    }
+
+    fn offset(&mut self) -> Result<usize, ErrorStatus> {
+        Ok(self.pos)
+    }
+
+    fn size(&mut self) -> Result<usize, ErrorStatus> {
+        Ok(self.len)
+    }
+
+    fn set_append(&mut self, append: bool) -> Result<(), ErrorStatus> {
+        self.append = append;
+        Ok(())
+    }
+
+    fn close(&mut self) -> Result<(), ErrorStatus> {
+        Ok(())
+    }
+}
+
+/// A write only stream over a growing buffer allocated with `malloc`, see `open_memstream`.
+///
+/// The buffer and the size of its contents are published to the caller on each flush and on close,
+/// the caller is responsible for freeing the buffer after the stream is closed.
+#[derive(Debug)]
+pub(crate) struct MemStream {
+    bufp: *mut *mut c_char,
+    sizep: *mut usize,
+    buf: *mut u8,
+    /// The size of `buf`, always more than `len` to keep room for a NUL
+    cap: usize,
+    len: usize,
+    pos: usize,
+}
+
+// The published pointers are only written through the stream that owns them, like a borrowed `FileBuffer`
+unsafe impl Send for MemStream {}
+unsafe impl Sync for MemStream {}
+
+impl MemStream {
+    const INITIAL_CAP: usize = 64;
+
+    /// Creates an empty stream publishing to `bufp` and `sizep`.
+    pub(crate) fn new(bufp: *mut *mut c_char, sizep: *mut usize) -> Result<Self, ErrorStatus> {
+        let buf = malloc(Self::INITIAL_CAP).cast::<u8>();
+        if buf.is_null() {
+            return Err(ErrorStatus::OutOfMemory);
+        }
+
+        unsafe { *buf = 0 };
+        let this = Self {
+            bufp,
+            sizep,
+            buf,
+            cap: Self::INITIAL_CAP,
+            len: 0,
+            pos: 0,
+        };
+        this.publish();
+        Ok(this)
+    }
+
+    /// Stores the buffer in `*bufp` and the size of the contents up to the current position in `*sizep`.
+    fn publish(&self) {
+        unsafe {
+            *self.bufp = self.buf.cast();
+            *self.sizep = self.len.min(self.pos);
+        }
+    }
+
+    /// Grows the buffer so that it can hold at least `cap` bytes.
+    fn reserve(&mut self, cap: usize) -> Result<(), ErrorStatus> {
+        if cap <= self.cap {
+            return Ok(());
+        }
+
+        let cap = cap.max(self.cap * 2);
+        let buf = malloc(cap).cast::<u8>();
+        if buf.is_null() {
+            return Err(ErrorStatus::OutOfMemory);
+        }
+
+        unsafe {
+            core::ptr::copy_nonoverlapping(self.buf, buf, self.len + 1);
+            free(self.buf.cast::<c_void>());
+        }
+        self.buf = buf;
+        self.cap = cap;
+        Ok(())
+    }
+}
+
+impl FileBackend for MemStream {
+    fn read(&mut self, _: &mut [u8]) -> Result<usize, ErrorStatus> {
+        Err(ErrorStatus::UnknownResource)
+    }
+
+    fn write(&mut self, bytes: &[u8]) -> Result<usize, ErrorStatus> {
+        let end = self
+            .pos
+            .checked_add(bytes.len())
+            .ok_or(ErrorStatus::OutOfMemory)?;
+        self.reserve(end + 1)?;
+
+        unsafe {
+            // Writing past the end of the contents fills the gap with zeros
+            if self.pos > self.len {
+                self.buf.add(self.len).write_bytes(0, self.pos - self.len);
+            }
+
+            core::ptr::copy_nonoverlapping(bytes.as_ptr(), self.buf.add(self.pos), bytes.len());
+            self.pos = end;
+            self.len = self.len.max(end);
+            *self.buf.add(self.len) = 0;
+        }
+        Ok(bytes.len())
+    }
+
+    fn seek(&mut self, wrench: SeekPosition) -> Result<(), ErrorStatus> {
+        self.pos = seek_in(self.pos, self.len, wrench)?;
+        Ok(())
+    }
+
+    fn offset(&mut self) -> Result<usize, ErrorStatus> {
+        Ok(self.pos)
+    }
+
+    fn size(&mut self) -> Result<usize, ErrorStatus> {
+        Ok(self.len)
+    }
+
+    fn set_append(&mut self, _: bool) -> Result<(), ErrorStatus> {
+        Ok(())
+    }
+
+    fn sync(&mut self) -> Result<(), ErrorStatus> {
+        self.publish();
+        Ok(())
+    }
+
+    fn close(&mut self) -> Result<(), ErrorStatus> {
+        self.publish();
+        // The buffer now belongs to the caller
+        self.buf = null_mut();
+        Ok(())
+    }
+}
@@ -0,0 +1,60 @@
+//! Custom printf conversion specifiers, see `include/printf.h`.
+
+use core::{
+    ffi::c_int,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use alloc::vec::Vec;
+use safa_api::{errors::ErrorStatus, sync::locks::Mutex};
+
+use crate::errno::set_error;
+
+pub use salibc_fmt::custom::*;
+
+static SPECIFIERS: Mutex<Vec<(u8, CustomSpecifier)>> = Mutex::new(Vec::new());
+/// Whether [`SPECIFIERS`] is not empty, so that printf doesn't lock it for every conversion when nothing is registered.
+static HAS_SPECIFIERS: AtomicBool = AtomicBool::new(false);
+
+/// The conversions registered with [`safa_register_printf_specifier`].
+pub(crate) struct RegisteredSpecifiers;
+
+impl CustomSpecifiers for RegisteredSpecifiers {
+    fn find(&self, spec: u8) -> Option<CustomSpecifier> {
+        if !HAS_SPECIFIERS.load(Ordering::Acquire) {
+            return None;
+        }
+        SPECIFIERS.lock().find(spec)
+    }
+}
+
+/// Registers `handler` to format the conversion specifier `spec`, replacing the previous handler if any,
+/// a null `handler` unregisters the conversion.
+#[unsafe(no_mangle)]
+pub extern "C" fn safa_register_printf_specifier(
+    spec: c_int,
+    handler: Option<SafaPrintfFunction>,
+    arginfo: Option<SafaPrintfArgInfoFunction>,
+) -> c_int {
+    let Ok(spec) = u8::try_from(spec) else {
+        set_error(ErrorStatus::InvalidArgument);
+        return -1;
+    };
+
+    let mut specifiers = SPECIFIERS.lock();
+    match (handler, arginfo) {
+        (Some(handler), Some(arginfo)) => {
+            specifiers.retain(|(s, _)| *s != spec);
+            // SAFETY: it is up to the caller to pass functions behaving as `include/printf.h` describes
+            let custom = unsafe { CustomSpecifier::new(handler, arginfo) };
+            specifiers.push((spec, custom));
+        }
+        (None, _) => specifiers.retain(|(s, _)| *s != spec),
+        (Some(_), None) => {
+            set_error(ErrorStatus::InvalidArgument);
+            return -1;
+        }
+    }
+    HAS_SPECIFIERS.store(!specifiers.is_empty(), Ordering::Release);
+    0
+}
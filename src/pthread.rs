@@ -1,4 +1,9 @@
-use core::{ffi::c_int, num::NonZero, sync::atomic::AtomicU32};
+use core::{
+    ffi::c_int,
+    num::NonZero,
+    sync::atomic::{AtomicU32, Ordering},
+    time::Duration,
+};
 
 use alloc::{boxed::Box, vec::Vec};
 use safa_api::{
@@ -336,3 +341,102 @@ pub extern "C" fn pthread_cond_broadcast(condvar: *mut PCondvar) -> c_int {
     try_errno!(futex_wake(&condvar.val, usize::MAX), -1);
     0
 }
+
+/// A lock the thread holding it can lock again, unlocked once it has been unlocked as many times as it was locked.
+#[derive(Debug)]
+pub(crate) struct RecursiveLock {
+    /// [`Self::UNLOCKED`], [`Self::LOCKED`] or [`Self::CONTENDED`] if other threads may be waiting for it
+    state: AtomicU32,
+    /// The thread holding the lock, [`Self::NO_OWNER`] while unlocked
+    owner: AtomicU32,
+    /// How many times the owner locked it, only accessed by the owner
+    count: AtomicU32,
+}
+
+impl RecursiveLock {
+    const UNLOCKED: u32 = 0;
+    const LOCKED: u32 = 1;
+    const CONTENDED: u32 = 2;
+    const NO_OWNER: u32 = u32::MAX;
+
+    pub(crate) const fn new() -> Self {
+        Self {
+            state: AtomicU32::new(Self::UNLOCKED),
+            owner: AtomicU32::new(Self::NO_OWNER),
+            count: AtomicU32::new(0),
+        }
+    }
+
+    /// Locks again if the current thread already holds the lock, returns whether it did.
+    fn relock(&self, me: PThreadID) -> bool {
+        // Only the owner stores its own id, and it is cleared before the lock is released
+        if self.owner.load(Ordering::Relaxed) == me {
+            self.count.fetch_add(1, Ordering::Relaxed);
+            return true;
+        }
+        false
+    }
+
+    fn acquired(&self, me: PThreadID) {
+        self.owner.store(me, Ordering::Relaxed);
+        self.count.store(1, Ordering::Relaxed);
+    }
+
+    /// Locks, waiting for the thread holding the lock to unlock it first.
+    pub(crate) fn lock(&self) {
+        let me = pthread_self();
+        if self.relock(me) {
+            return;
+        }
+
+        if self
+            .state
+            .compare_exchange(
+                Self::UNLOCKED,
+                Self::LOCKED,
+                Ordering::Acquire,
+                Ordering::Relaxed,
+            )
+            .is_err()
+        {
+            while self.state.swap(Self::CONTENDED, Ordering::Acquire) != Self::UNLOCKED {
+                _ = futex_wait(&self.state, Self::CONTENDED, Duration::MAX);
+            }
+        }
+        self.acquired(me);
+    }
+
+    /// Locks without waiting, returns `false` if another thread holds the lock.
+    pub(crate) fn try_lock(&self) -> bool {
+        let me = pthread_self();
+        if self.relock(me) {
+            return true;
+        }
+
+        let locked = self
+            .state
+            .compare_exchange(
+                Self::UNLOCKED,
+                Self::LOCKED,
+                Ordering::Acquire,
+                Ordering::Relaxed,
+            )
+            .is_ok();
+        if locked {
+            self.acquired(me);
+        }
+        locked
+    }
+
+    /// Unlocks once, the current thread must hold the lock.
+    pub(crate) fn unlock(&self) {
+        if self.count.fetch_sub(1, Ordering::Relaxed) != 1 {
+            return;
+        }
+
+        self.owner.store(Self::NO_OWNER, Ordering::Relaxed);
+        if self.state.swap(Self::UNLOCKED, Ordering::Release) == Self::CONTENDED {
+            _ = futex_wake(&self.state, 1);
+        }
+    }
+}
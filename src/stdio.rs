@@ -1,22 +1,31 @@
 use core::{
-    ffi::{CStr, VaList, c_char, c_int, c_uint, c_void},
+    ffi::{CStr, VaList, c_char, c_int, c_long, c_uint, c_void},
     mem::MaybeUninit,
     ptr::null_mut,
 };
 
-use safa_api::{abi::fs::OpenOptions, errors::ErrorStatus, syscalls::fs};
+use safa_api::{abi::fs::OpenOptions, errors::ErrorStatus, sync::locks::Mutex, syscalls::fs};
 
-use crate::parse::{BufReader, CReader};
+use crate::parse::{BufReader, CReader, ScanfError};
 use crate::{
     SyncUnsafeCell,
-    file::{self, BufferingOption, File, SeekPosition},
+    cookie::{
+        CookieFile, CookieIoFunctions, FunCloseFunction, FunIoFunctions, FunReadFunction,
+        FunSeekFunction, FunWriteFunction,
+    },
+    errno::set_error,
+    fcntl::{O_ACCMODE, O_RDONLY, O_WRONLY},
+    file::{self, BufferingOption, File, FileBuffer, FileGuard, FileState, SeekPosition},
     format::BufWriter,
+    memstream::{MemFile, MemStream},
+    printf::RegisteredSpecifiers,
+    stdlib::{malloc, realloc},
     string::strlen,
-    try_errno,
+    try_errno, unistd,
 };
 
 extern crate alloc;
-use alloc::{boxed::Box, vec::Vec};
+use alloc::{boxed::Box, format, string::String, vec::Vec};
 
 #[used]
 pub static STDIN_RAW: SyncUnsafeCell<MaybeUninit<File>> =
@@ -28,6 +37,12 @@ pub static STDOUT_RAW: SyncUnsafeCell<MaybeUninit<File>> =
 pub static STDERR_RAW: SyncUnsafeCell<MaybeUninit<File>> =
     SyncUnsafeCell::new(MaybeUninit::uninit());
 
+pub const EOF: c_int = -1;
+
+pub const _IONBF: c_int = BufferingOption::None as c_int;
+pub const _IOFBF: c_int = BufferingOption::Buffered as c_int;
+pub const _IOLBF: c_int = BufferingOption::LineBuffered as c_int;
+
 #[derive(Debug)]
 pub struct StdIo(pub SyncUnsafeCell<*mut File>);
 unsafe impl Send for StdIo {}
@@ -40,11 +55,141 @@ pub static stdout: StdIo = StdIo(SyncUnsafeCell::new(null_mut()));
 #[unsafe(no_mangle)]
 pub static stderr: StdIo = StdIo(SyncUnsafeCell::new(null_mut()));
 
+// ==========================
+// Open streams
+// ==========================
+
+/// An open stream, only accessed while locked, see [`FileGuard`].
+#[derive(Debug)]
+struct OpenStream(*mut File);
+unsafe impl Send for OpenStream {}
+
+/// Every open stream, flushed by `fflush(NULL)` and closed by [`crate::exit::exit`].
+///
+/// Always locked before the streams themselves.
+static STREAMS: Mutex<Vec<OpenStream>> = Mutex::new(Vec::new());
+
+/// Adds `stream` to the open streams, returns `stream`.
+pub(crate) fn register_stream(stream: *mut File) -> *mut File {
+    STREAMS.lock().push(OpenStream(stream));
+    stream
+}
+
+/// Allocates a stream with the state `file` and adds it to the open streams, the stream is freed by [`fclose`].
+fn open_stream(file: FileState) -> *mut File {
+    register_stream(Box::into_raw(Box::new(File::new(file))))
+}
+
+fn unregister_stream(stream: *mut File) {
+    STREAMS.lock().retain(|open| open.0 != stream);
+}
+
+/// Returns whether `stream` is one of the standard streams, which live in `STD*_RAW` instead of being allocated.
+fn is_std_stream(stream: *mut File) -> bool {
+    [STDIN_RAW.get(), STDOUT_RAW.get(), STDERR_RAW.get()]
+        .into_iter()
+        .any(|raw| raw.cast::<File>() == stream)
+}
+
+/// Frees the closed `stream`, unless it is one of the standard streams.
+///
+/// # Safety
+/// `stream` must have been returned by [`open_stream`] or be a standard stream, and must not be used again.
+unsafe fn free_stream(stream: *mut File) {
+    if !is_std_stream(stream) {
+        drop(unsafe { Box::from_raw(stream) });
+    }
+}
+
+/// Flushes every open stream for `fflush(NULL)`, returns the first failure if any.
+///
+/// Waits for the streams held by other threads as `fflush` does, unlike [`close_streams`].
+fn flush_streams() -> Result<(), ErrorStatus> {
+    let mut results = Ok(());
+    for stream in STREAMS.lock().iter() {
+        let flushed = unsafe { FileGuard::lock(stream.0) }.flush();
+        results = results.and(flushed);
+    }
+    results
+}
+
+/// Flushes and closes every open stream, called by [`crate::exit::exit`] once the `atexit` handlers ran.
+///
+/// Streams held by other threads are skipped instead of waited for, as those threads may never get to unlock them.
+pub(crate) fn close_streams() {
+    let streams = core::mem::take(&mut *STREAMS.lock());
+    for stream in streams {
+        if let Some(mut guard) = unsafe { FileGuard::try_lock(stream.0) } {
+            _ = unsafe { guard.close_ref() };
+        }
+    }
+}
+
 // ==========================
 // File management
 // ==========================
 
-fn fopen_inner(filename: *const c_char, mode: *const c_char) -> Option<File> {
+/// A parsed `fopen` mode string.
+#[derive(Debug, Clone, Copy)]
+struct FopenMode {
+    options: OpenOptions,
+    /// `w`, the file is truncated when opened
+    truncate: bool,
+    /// `a`, every write lands at the end of the file
+    append: bool,
+    /// `x`, opening fails if the file already exists
+    create_new: bool,
+}
+
+impl FopenMode {
+    /// Parses `r`, `w` or `a` followed by any of `+`, `b`, `x` (only after `w`) and `e`,
+    /// fails with [`ErrorStatus::InvalidArgument`] on anything else.
+    fn parse(mode: &[u8]) -> Result<Self, ErrorStatus> {
+        let (&first, flags) = mode.split_first().ok_or(ErrorStatus::InvalidArgument)?;
+
+        let mut this = Self {
+            options: OpenOptions::from_bits(0),
+            truncate: false,
+            append: false,
+            create_new: false,
+        };
+
+        let (read, write) = match first {
+            b'r' => (OpenOptions::READ, OpenOptions::READ | OpenOptions::WRITE),
+            b'w' => {
+                this.truncate = true;
+                (
+                    OpenOptions::WRITE | OpenOptions::CREATE_FILE,
+                    OpenOptions::READ | OpenOptions::WRITE | OpenOptions::CREATE_FILE,
+                )
+            }
+            b'a' => {
+                this.append = true;
+                (
+                    OpenOptions::WRITE | OpenOptions::CREATE_FILE,
+                    OpenOptions::READ | OpenOptions::WRITE | OpenOptions::CREATE_FILE,
+                )
+            }
+            _ => return Err(ErrorStatus::InvalidArgument),
+        };
+
+        this.options = read;
+        for flag in flags {
+            match flag {
+                b'+' => this.options = write,
+                b'b' => {}
+                b'x' if first == b'w' => this.create_new = true,
+                // FIXME: close-on-exec is accepted but has no effect,
+                // resources are inherited by every process spawned with `CLONE_RESOURCES`
+                b'e' => {}
+                _ => return Err(ErrorStatus::InvalidArgument),
+            }
+        }
+        Ok(this)
+    }
+}
+
+fn fopen_inner(filename: *const c_char, mode: *const c_char) -> Option<FileState> {
     let cstr_path = unsafe { CStr::from_ptr(filename) };
     let cstr_mode = unsafe { CStr::from_ptr(mode) };
 
@@ -52,50 +197,125 @@ fn fopen_inner(filename: *const c_char, mode: *const c_char) -> Option<File> {
         cstr_path.to_str().map_err(|_| ErrorStatus::InvalidStr),
         None
     );
+    let mode = try_errno!(FopenMode::parse(cstr_mode.to_bytes()), None);
 
-    let mut options = OpenOptions::from_bits(0);
-    let mut append = false;
-    let mut create_new = false;
-
-    for b in cstr_mode.to_bytes() {
-        match b {
-            b'w' => options = options | OpenOptions::WRITE | OpenOptions::CREATE_FILE,
-            b'a' => {
-                options = options | OpenOptions::CREATE_FILE;
-                append = true
-            }
-            b'r' => options = options | OpenOptions::READ,
-            b'x' => create_new = true,
-            _ => continue,
-        }
-    }
-
-    if create_new
-        && File::open(path, OpenOptions::from_bits(0)) != Err(ErrorStatus::NoSuchAFileOrDirectory)
-    // File exists
-    {
-        return None;
-    }
+    let f = if mode.create_new {
+        FileState::open_new(path, mode.options)
+    } else {
+        FileState::open(path, mode.options)
+    };
 
-    let mut f = try_errno!(File::open(path, options), None);
-    if append {
-        f.seek(SeekPosition::End(0));
+    let mut f = try_errno!(f, None);
+    if mode.truncate {
+        try_errno!(f.truncate(0), None);
     }
+    try_errno!(f.set_append(mode.append), None);
     Some(f)
 }
 
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn fopen(filename: *const c_char, mode: *const c_char) -> *mut File {
     match fopen_inner(filename, mode) {
-        Some(o) => Box::leak(Box::new(o)),
+        Some(o) => open_stream(o),
         None => null_mut(),
     }
 }
 
+/// Opens a stream over the `size` bytes at `buf`, or over `size` allocated bytes if `buf` is null.
+#[unsafe(no_mangle)]
+pub extern "C" fn fmemopen(buf: *mut c_void, size: usize, mode: *const c_char) -> *mut File {
+    let mode = unsafe { CStr::from_ptr(mode) };
+    let mode = try_errno!(FopenMode::parse(mode.to_bytes()), null_mut());
+    if size == 0 {
+        set_error(ErrorStatus::InvalidArgument);
+        return null_mut();
+    }
+
+    let buf = if buf.is_null() {
+        FileBuffer::alloc(size)
+    } else {
+        FileBuffer::Borrowed(buf.cast(), size)
+    };
+
+    let backend = MemFile::new(
+        buf,
+        mode.options.contains(OpenOptions::READ),
+        mode.options.contains(OpenOptions::WRITE),
+        mode.truncate,
+        mode.append,
+    );
+    open_stream(FileState::from_backend(Box::new(backend)))
+}
+
+/// Opens a write only stream over a growing buffer,
+/// the buffer and the size of its contents are stored in `*bufp` and `*sizep` on each flush and on close.
+#[unsafe(no_mangle)]
+pub extern "C" fn open_memstream(bufp: *mut *mut c_char, sizep: *mut usize) -> *mut File {
+    if bufp.is_null() || sizep.is_null() {
+        set_error(ErrorStatus::InvalidArgument);
+        return null_mut();
+    }
+
+    let backend = try_errno!(MemStream::new(bufp, sizep), null_mut());
+    open_stream(FileState::from_backend(Box::new(backend)))
+}
+
+/// Opens a stream calling `io_funcs` with `cookie` to read, write, seek and close.
+#[unsafe(no_mangle)]
+pub extern "C" fn fopencookie(
+    cookie: *mut c_void,
+    mode: *const c_char,
+    io_funcs: CookieIoFunctions,
+) -> *mut File {
+    let mode = unsafe { CStr::from_ptr(mode) };
+    let mode = try_errno!(FopenMode::parse(mode.to_bytes()), null_mut());
+
+    let backend = CookieFile::from_cookie(
+        cookie,
+        io_funcs,
+        mode.options.contains(OpenOptions::READ),
+        mode.options.contains(OpenOptions::WRITE),
+    );
+
+    let mut f = FileState::from_backend(Box::new(backend));
+    try_errno!(f.set_append(mode.append), null_mut());
+    open_stream(f)
+}
+
+/// Opens a stream calling the given functions with `cookie` to read, write, seek and close,
+/// the stream is readable if `readfn` isn't null and writable if `writefn` isn't null.
+#[unsafe(no_mangle)]
+pub extern "C" fn funopen(
+    cookie: *const c_void,
+    readfn: Option<FunReadFunction>,
+    writefn: Option<FunWriteFunction>,
+    seekfn: Option<FunSeekFunction>,
+    closefn: Option<FunCloseFunction>,
+) -> *mut File {
+    if readfn.is_none() && writefn.is_none() {
+        set_error(ErrorStatus::InvalidArgument);
+        return null_mut();
+    }
+
+    let backend = CookieFile::from_fun(
+        cookie.cast_mut(),
+        FunIoFunctions {
+            read: readfn,
+            write: writefn,
+            seek: seekfn,
+            close: closefn,
+        },
+    );
+    open_stream(FileState::from_backend(Box::new(backend)))
+}
+
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn fclose(file: *mut File) -> c_int {
-    let boxed = unsafe { Box::from_raw(file) };
-    try_errno!(boxed.close(), -1);
+    // Unregistered before locking the stream, see `STREAMS`
+    unregister_stream(file);
+    let results = unsafe { FileGuard::lock(file).close_ref() };
+    unsafe { free_stream(file) };
+    try_errno!(results, -1);
     0
 }
 
@@ -105,25 +325,57 @@ pub extern "C" fn freopen(
     mode: *const c_char,
     file: *mut File,
 ) -> *mut File {
-    let mut old = unsafe { Box::from_raw(file) };
-    unsafe { try_errno!(old.close_ref(), null_mut()) };
-    let Some(new) = fopen_inner(filename, mode) else {
-        return null_mut();
-    };
+    let mut old = unsafe { FileGuard::lock(file) };
+    match unsafe { old.close_ref() }.map(|()| fopen_inner(filename, mode)) {
+        Ok(Some(new)) => {
+            // The lock and whoever holds it are kept, only what the stream refers to changes
+            *old = new;
+            file
+        }
+        results => {
+            // The stream is released on failure
+            drop(old);
+            unregister_stream(file);
+            unsafe { free_stream(file) };
+            if let Err(e) = results {
+                set_error(e);
+            }
+            null_mut()
+        }
+    }
+}
 
-    *old = new;
+#[unsafe(no_mangle)]
+pub extern "C" fn fdopen(fildes: c_int, mode: *const c_char) -> *mut File {
+    let mode = unsafe { CStr::from_ptr(mode) };
+    let mode = try_errno!(FopenMode::parse(mode.to_bytes()), null_mut());
+    let desc = try_errno!(unistd::get(fildes), null_mut());
+
+    // The stream can't be given access the descriptor doesn't have
+    let access = desc.lock().flags & O_ACCMODE;
+    if (mode.options.contains(OpenOptions::READ) && access == O_WRONLY)
+        || (mode.options.contains(OpenOptions::WRITE) && access == O_RDONLY)
+    {
+        set_error(ErrorStatus::InvalidArgument);
+        return null_mut();
+    }
 
-    Box::leak(old)
+    // The stream shares the position of the descriptor
+    let mut f = FileState::from_descriptor(fildes, desc);
+    if mode.append {
+        try_errno!(f.set_append(true), null_mut());
+    }
+    open_stream(f)
 }
 
+/// Returns the descriptor underlying `stream`,
+/// if there is none yet the stream's resource is given a descriptor which is closed with the stream.
+///
+/// Fails with `EBADF` if `stream` isn't backed by a resource.
 #[unsafe(no_mangle)]
-pub extern "C" fn fdopen(fildes: c_int, mode: *const c_char) -> *mut File {
-    _ = mode;
-    Box::into_raw(Box::new(File::from_res(
-        fildes as u32,
-        BufferingOption::None,
-        false,
-    )))
+pub extern "C" fn fileno(stream: *mut File) -> c_int {
+    let mut stream = unsafe { FileGuard::lock(stream) };
+    try_errno!(stream.fileno(), -1)
 }
 
 #[unsafe(no_mangle)]
@@ -157,11 +409,28 @@ pub unsafe extern "C" fn fread(
     count: usize,
     stream: *mut File,
 ) -> usize {
-    let stream = unsafe { &mut *stream };
+    if size == 0 {
+        return 0;
+    }
+
+    let mut stream = unsafe { FileGuard::lock(stream) };
     let buf = unsafe {
         core::slice::from_raw_parts_mut(ptr.cast::<u8>(), (size * count).min(isize::MAX as usize))
     };
-    try_errno!(stream.read(buf).map(|c| c / size), core::usize::MAX)
+
+    // A single read stops at the end of the buffered data, keep going until the end of the file
+    let mut read = 0;
+    while read < buf.len() {
+        match stream.read(&mut buf[read..]) {
+            Ok(0) => break,
+            Ok(amount) => read += amount,
+            Err(e) => {
+                set_error(e);
+                break;
+            }
+        }
+    }
+    read / size
 }
 
 #[unsafe(no_mangle)]
@@ -171,16 +440,62 @@ pub unsafe extern "C" fn fwrite(
     count: usize,
     stream: *mut File,
 ) -> usize {
-    let stream = unsafe { &mut *stream };
-    let buf = unsafe { core::slice::from_raw_parts(ptr.cast::<u8>(), size * count) };
-    try_errno!(stream.write(buf).map(|c| c / size), core::usize::MAX)
+    let _guard = unsafe { FileGuard::lock(stream) };
+    unsafe { fwrite_unlocked(ptr, size, count, stream) }
+}
+
+/// Same as [`fwrite`] without locking `stream`, see [`flockfile`].
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn fwrite_unlocked(
+    ptr: *const c_void,
+    size: usize,
+    count: usize,
+    stream: *mut File,
+) -> usize {
+    if size == 0 || count == 0 {
+        return 0;
+    }
+
+    let Some(len) = size
+        .checked_mul(count)
+        .filter(|len| *len <= isize::MAX as usize)
+    else {
+        set_error(ErrorStatus::InvalidArgument);
+        return 0;
+    };
+
+    let stream = unsafe { &mut *(*stream).state() };
+    let buf = unsafe { core::slice::from_raw_parts(ptr.cast::<u8>(), len) };
+
+    // A write may be short, keep going until everything is written or writing fails
+    let mut written = 0;
+    while written < buf.len() {
+        match stream.write(&buf[written..]) {
+            Ok(amount) => written += amount,
+            Err(e) => {
+                set_error(e);
+                break;
+            }
+        }
+    }
+    // Only complete items count
+    written / size
 }
 
 #[unsafe(no_mangle)]
 pub extern "C" fn fgetc(stream: *mut File) -> c_int {
+    let _guard = unsafe { FileGuard::lock(stream) };
+    getc_unlocked(stream)
+}
+
+/// Same as [`fgetc`] without locking `stream`, see [`flockfile`].
+#[unsafe(no_mangle)]
+pub extern "C" fn getc_unlocked(stream: *mut File) -> c_int {
     let mut buf = [0u8; 1];
-    let stream = unsafe { &mut *stream };
-    try_errno!(stream.read(&mut buf), -1);
+    let stream = unsafe { &mut *(*stream).state() };
+    if try_errno!(stream.read(&mut buf), EOF) == 0 {
+        return EOF;
+    }
     buf[0] as c_int
 }
 
@@ -199,27 +514,49 @@ pub extern "C" fn putchar(c: c_int) -> c_int {
     unsafe { fputc(c, *stdout.0.get()) }
 }
 
+#[unsafe(no_mangle)]
+pub extern "C" fn getchar_unlocked() -> c_int {
+    unsafe { getc_unlocked(*stdin.0.get()) }
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn putchar_unlocked(c: c_int) -> c_int {
+    unsafe { putc_unlocked(c, *stdout.0.get()) }
+}
+
 #[unsafe(no_mangle)]
 pub extern "C" fn ungetc(c: c_int, stream: *mut File) -> c_int {
-    let _ = (c, stream);
-    todo!("ungetc")
+    if c == EOF {
+        return EOF;
+    }
+
+    let mut stream = unsafe { FileGuard::lock(stream) };
+    let c = c as u8;
+    if stream.unread(c) { c as c_int } else { EOF }
 }
 
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn fputc(c: c_int, stream: *mut File) -> c_int {
-    let stream = unsafe { &mut *stream };
+    let _guard = unsafe { FileGuard::lock(stream) };
+    unsafe { putc_unlocked(c, stream) }
+}
+
+/// Same as [`fputc`] without locking `stream`, see [`flockfile`].
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn putc_unlocked(c: c_int, stream: *mut File) -> c_int {
+    let stream = unsafe { &mut *(*stream).state() };
     let buf = [c as u8];
     loop {
-        let r = try_errno!(stream.write(&buf), -1);
+        let r = try_errno!(stream.write(&buf), EOF);
         if r == 1 {
-            return 0;
+            return buf[0] as c_int;
         }
     }
 }
 
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn fputs(s: *const c_char, stream: *mut File) -> c_int {
-    let stream = unsafe { &mut *stream };
+    let mut stream = unsafe { FileGuard::lock(stream) };
     let cstr = unsafe { CStr::from_ptr(s) };
     let mut bytes = cstr.to_bytes();
 
@@ -235,8 +572,18 @@ pub unsafe extern "C" fn fputs(s: *const c_char, stream: *mut File) -> c_int {
 
 #[unsafe(no_mangle)]
 pub extern "C" fn fgets(s: *mut c_char, size: c_int, stream: *mut File) -> *mut c_char {
-    let stream = unsafe { &mut *stream };
+    let _guard = unsafe { FileGuard::lock(stream) };
+    fgets_unlocked(s, size, stream)
+}
+
+/// Same as [`fgets`] without locking `stream`, see [`flockfile`].
+#[unsafe(no_mangle)]
+pub extern "C" fn fgets_unlocked(s: *mut c_char, size: c_int, stream: *mut File) -> *mut c_char {
+    let stream = unsafe { &mut *(*stream).state() };
     let size = size as c_uint as usize;
+    if size == 0 {
+        return null_mut();
+    }
 
     let buf = unsafe { core::slice::from_raw_parts_mut(s.cast::<u8>(), size) };
     let max = size - 1;
@@ -245,18 +592,95 @@ pub extern "C" fn fgets(s: *mut c_char, size: c_int, stream: *mut File) -> *mut
         stream.read_bytes_until_or_eof(&mut buf[..max], |c| c == b'\n'),
         null_mut()
     );
+    // Nothing could be read before the end of file
+    if amount == 0 && max != 0 {
+        return null_mut();
+    }
 
     buf[amount] = 0;
     s
 }
 
+/// The size of the first buffer allocated by [`getdelim`]
+const GETDELIM_INITIAL_LEN: usize = 128;
+
+/// Reads from `stream` up to and including `delim` into `*lineptr`, which is NUL terminated,
+/// `*lineptr` is grown with `realloc` (or allocated if it is null) and its size is stored in `*n`.
+///
+/// Returns the amount of bytes read not counting the NUL, -1 at end of file or on failure.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn getdelim(
+    lineptr: *mut *mut c_char,
+    n: *mut usize,
+    delim: c_int,
+    stream: *mut File,
+) -> isize {
+    if lineptr.is_null() || n.is_null() {
+        set_error(ErrorStatus::InvalidArgument);
+        return -1;
+    }
+
+    let mut stream = unsafe { FileGuard::lock(stream) };
+    let (lineptr, n) = unsafe { (&mut *lineptr, &mut *n) };
+    if (*lineptr).is_null() {
+        *n = 0;
+    }
+
+    let delim = delim as u8;
+    let mut len = 0;
+    loop {
+        let mut byte = [0u8; 1];
+        let read = try_errno!(stream.read(&mut byte), -1);
+
+        // Room for the byte and the NUL
+        if len + 2 > *n || (*lineptr).is_null() {
+            let size = (*n * 2).max(len + 2).max(GETDELIM_INITIAL_LEN);
+            let new = realloc((*lineptr).cast(), size).cast::<c_char>();
+            if new.is_null() {
+                set_error(ErrorStatus::OutOfMemory);
+                return -1;
+            }
+
+            *lineptr = new;
+            *n = size;
+        }
+
+        if read == 0 {
+            break;
+        }
+
+        unsafe { *(*lineptr).add(len) = byte[0] as c_char };
+        len += 1;
+        if byte[0] == delim {
+            break;
+        }
+    }
+
+    unsafe { *(*lineptr).add(len) = 0 };
+    if len == 0 {
+        return -1;
+    }
+    len as isize
+}
+
+/// Same as [`getdelim`] with `'\n'` as the delimiter.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn getline(
+    lineptr: *mut *mut c_char,
+    n: *mut usize,
+    stream: *mut File,
+) -> isize {
+    unsafe { getdelim(lineptr, n, b'\n' as c_int, stream) }
+}
+
 #[unsafe(no_mangle)]
 extern "C" fn fgetline(file: *mut File, len: *mut usize) -> *mut c_char {
     unsafe {
         let mut buf = Vec::new();
 
         try_errno!(
-            (*file).read_bytes_until_or_eof_alloc(&mut buf, usize::MAX, |c| c == b'\n'),
+            FileGuard::lock(file)
+                .read_bytes_until_or_eof_alloc(&mut buf, usize::MAX, |c| c == b'\n'),
             null_mut()
         );
 
@@ -268,49 +692,137 @@ extern "C" fn fgetline(file: *mut File, len: *mut usize) -> *mut c_char {
     }
 }
 
+/// Changes how `file` is buffered, `custom_buffer` is used as the buffer if it isn't null,
+/// otherwise a buffer of at least `size` bytes is allocated.
 #[unsafe(no_mangle)]
-// TODO: add Custom buffering
-extern "C" fn setvbuf(file: *mut File, custom_buffer: *mut u8, mode: u8, size: usize) -> c_int {
-    let Some(mode) = BufferingOption::from_u8(mode) else {
+pub extern "C" fn setvbuf(
+    file: *mut File,
+    custom_buffer: *mut c_char,
+    mode: c_int,
+    size: usize,
+) -> c_int {
+    let Some(mode) = u8::try_from(mode).ok().and_then(BufferingOption::from_u8) else {
+        set_error(ErrorStatus::InvalidArgument);
         return -1;
     };
 
-    assert_eq!(
-        custom_buffer,
-        null_mut(),
-        "Custom buffering isn't yet supported"
-    );
-    unsafe {
-        (*file).set_buffering(mode, size);
+    let mut file = unsafe { FileGuard::lock(file) };
+    match mode {
+        BufferingOption::None => file.set_buffering(mode, 0),
+        _ if custom_buffer.is_null() => file.set_buffering(mode, size),
+        _ if size == 0 => {
+            set_error(ErrorStatus::InvalidArgument);
+            return -1;
+        }
+        _ => unsafe { file.set_buffering_borrowed(mode, custom_buffer.cast(), size) },
     }
-    return 0;
+    0
+}
+
+/// Makes `file` fully buffered using the `size` bytes at `buffer`, or unbuffered if `buffer` is null.
+#[unsafe(no_mangle)]
+pub extern "C" fn setbuffer(file: *mut File, buffer: *mut c_char, size: usize) {
+    let mode = if buffer.is_null() { _IONBF } else { _IOFBF };
+    setvbuf(file, buffer, mode, size);
+}
+
+/// Makes `file` line buffered.
+#[unsafe(no_mangle)]
+pub extern "C" fn setlinebuf(file: *mut File) {
+    setvbuf(file, null_mut(), _IOLBF, 0);
 }
 
 // ==========================
 // Positioning
 // ==========================
 
-#[unsafe(no_mangle)]
-pub extern "C" fn fseek(stream: *mut File, offset: c_int, whence: c_int) -> c_int {
-    let pos = match (whence, offset >= 0) {
-        (0, true) => SeekPosition::Start(offset as usize),
-        (0, false) => SeekPosition::End((-offset) as usize),
-        (1, true | false) => SeekPosition::Current(offset as isize),
-        (2, true) => SeekPosition::End(offset as usize),
-        (2, false) => SeekPosition::Start((-offset) as usize),
-        _ => return -1,
+pub type OffT = i64;
+
+pub const SEEK_SET: c_int = 0;
+pub const SEEK_CUR: c_int = 1;
+pub const SEEK_END: c_int = 2;
+
+/// An opaque file position as stored by [`fgetpos`].
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct FPos {
+    pos: OffT,
+}
+
+/// Converts `offset` relative to `whence` into an absolute position in a file at `current` with the size returned by `size`,
+/// fails with [`ErrorStatus::InvalidArgument`] if `whence` is invalid or the position would be negative.
+pub(crate) fn seek_position(
+    offset: OffT,
+    whence: c_int,
+    current: usize,
+    size: impl FnOnce() -> Result<usize, ErrorStatus>,
+) -> Result<SeekPosition, ErrorStatus> {
+    let offset = isize::try_from(offset).map_err(|_| ErrorStatus::InvalidArgument)?;
+    let base = match whence {
+        SEEK_SET => 0,
+        SEEK_CUR => current,
+        SEEK_END => size()?,
+        _ => return Err(ErrorStatus::InvalidArgument),
     };
 
-    unsafe {
-        (*stream).seek(pos);
-        0
+    let Some(pos) = base.checked_add_signed(offset) else {
+        return Err(ErrorStatus::InvalidArgument);
+    };
+    // Appending doesn't depend on the position, writes land at the end of the file regardless
+    Ok(SeekPosition::Start(pos))
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn fseeko(stream: *mut File, offset: OffT, whence: c_int) -> c_int {
+    let mut stream = unsafe { FileGuard::lock(stream) };
+    let current = try_errno!(stream.offset(), -1);
+    let pos = try_errno!(seek_position(offset, whence, current, || stream.size()), -1);
+    try_errno!(stream.seek(pos), -1);
+    0
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn fseek(stream: *mut File, offset: c_long, whence: c_int) -> c_int {
+    fseeko(stream, offset as OffT, whence)
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn ftello(stream: *mut File) -> OffT {
+    let mut stream = unsafe { FileGuard::lock(stream) };
+    try_errno!(stream.offset(), -1) as OffT
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn ftell(stream: *mut File) -> c_long {
+    ftello(stream) as c_long
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn fgetpos(stream: *mut File, pos: *mut FPos) -> c_int {
+    // `*pos` is left untouched on failure
+    let offset = ftello(stream);
+    if offset < 0 {
+        return -1;
     }
+
+    unsafe { *pos = FPos { pos: offset } };
+    0
 }
 
 #[unsafe(no_mangle)]
-pub extern "C" fn ftell(stream: *mut File) -> c_int {
-    let stream = unsafe { &*stream };
-    stream.offset() as c_int
+pub extern "C" fn fsetpos(stream: *mut File, pos: *const FPos) -> c_int {
+    let pos = unsafe { (*pos).pos };
+    fseeko(stream, pos, SEEK_SET)
+}
+
+/// Seeks `stream` to its start and clears its error indicator, even if seeking fails.
+#[unsafe(no_mangle)]
+pub extern "C" fn rewind(stream: *mut File) {
+    let mut stream = unsafe { FileGuard::lock(stream) };
+    if let Err(e) = stream.seek(SeekPosition::Start(0)) {
+        set_error(e);
+    }
+    stream.clear_error();
 }
 
 // ==========================
@@ -319,66 +831,170 @@ pub extern "C" fn ftell(stream: *mut File) -> c_int {
 
 #[unsafe(no_mangle)]
 pub extern "C" fn feof(stream: *mut File) -> c_int {
-    let stream = unsafe { &*stream };
+    let stream = unsafe { FileGuard::lock(stream) };
     stream.is_eof() as c_int
 }
 
 #[unsafe(no_mangle)]
 pub extern "C" fn ferror(stream: *mut File) -> c_int {
-    let _ = stream;
-    safa_api::printerrln!("ferror: Not yet implemented");
-    0
+    let stream = unsafe { FileGuard::lock(stream) };
+    stream.is_error() as c_int
 }
 
 #[unsafe(no_mangle)]
 pub extern "C" fn clearerr(stream: *mut File) {
-    let _ = stream;
-    safa_api::printerrln!("clearerr: Not yet implemented");
+    let mut stream = unsafe { FileGuard::lock(stream) };
+    stream.clear_error();
 }
 
+/// Flushes `stream`, or every open stream if `stream` is null.
 #[unsafe(no_mangle)]
 pub extern "C" fn fflush(stream: *mut File) -> c_int {
-    let stream = unsafe { &mut *stream };
+    if stream.is_null() {
+        try_errno!(flush_streams(), -1);
+        return 0;
+    }
+
+    let mut stream = unsafe { FileGuard::lock(stream) };
     try_errno!(stream.flush().map(|()| 0), -1)
 }
 
+// ==========================
+// Locking
+// ==========================
+
+/// Locks `stream` for the current thread, waiting for the thread holding it to unlock it first,
+/// every other operation on `stream` waits until it is unlocked as many times as it was locked.
+#[unsafe(no_mangle)]
+pub extern "C" fn flockfile(stream: *mut File) {
+    unsafe { (*stream).lock() };
+}
+
+/// Same as [`flockfile`] without waiting, returns non-zero if another thread holds `stream`.
+#[unsafe(no_mangle)]
+pub extern "C" fn ftrylockfile(stream: *mut File) -> c_int {
+    if unsafe { (*stream).try_lock() } {
+        0
+    } else {
+        -1
+    }
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn funlockfile(stream: *mut File) {
+    unsafe { (*stream).unlock() };
+}
+
 // ==========================
 // Temporary files
 // ==========================
 
+pub const L_TMPNAM: usize = 128;
+
+/// Creates a new file in the temporary directory opened for reading and writing,
+/// the file is removed when it is closed or when the process exits.
 #[unsafe(no_mangle)]
 pub extern "C" fn tmpfile() -> *mut File {
-    todo!("tmpfile")
+    let mut template = format!("{}/tmpXXXXXX", file::temp_dir()).into_bytes();
+    let random = template.len() - 6..template.len();
+
+    let file = try_errno!(file::create_temp_file(&mut template, random), null_mut());
+    let mut f = FileState::from_backend(Box::new(file));
+
+    // The path was already checked to be UTF-8 when the file was created
+    let path = String::from_utf8(template).map_err(|_| ErrorStatus::InvalidArgument);
+    f.remove_on_close(try_errno!(path, null_mut()));
+    open_stream(f)
 }
 
+static TMPNAM_BUF: SyncUnsafeCell<[c_char; L_TMPNAM]> = SyncUnsafeCell::new([0; L_TMPNAM]);
+
+/// Returns a path in the temporary directory which nothing exists at,
+/// stored in `s` which must be at least `L_tmpnam` bytes long or in a static buffer if `s` is null.
 #[unsafe(no_mangle)]
 pub extern "C" fn tmpnam(s: *mut c_char) -> *mut c_char {
-    let _ = s;
-    todo!("tmpnam")
+    let mut template = format!("{}/tmpXXXXXX", file::temp_dir()).into_bytes();
+    let random = template.len() - 6..template.len();
+    if template.len() >= L_TMPNAM {
+        set_error(ErrorStatus::StrTooLong);
+        return null_mut();
+    }
+
+    try_errno!(
+        file::create_temp(&mut template, random, |path| {
+            match fs::getdirentry(path) {
+                Err(ErrorStatus::NoSuchAFileOrDirectory) => Ok(()),
+                Ok(_) => Err(ErrorStatus::AlreadyExists),
+                Err(e) => Err(e),
+            }
+        }),
+        null_mut()
+    );
+
+    let s = if s.is_null() {
+        unsafe { (*TMPNAM_BUF.get()).as_mut_ptr() }
+    } else {
+        s
+    };
+
+    unsafe {
+        core::ptr::copy_nonoverlapping(template.as_ptr().cast(), s, template.len());
+        *s.add(template.len()) = 0;
+    }
+    s
 }
 
 // ==========================
-// Formatted output (varargs — intentionally unimplemented)
+// Formatted output
 // ==========================
 
+/// Converts a failure of [`crate::format::printf_to`] or [`crate::parse::scanf_from`] into the status errno is set to,
+/// `io` converts the failures of the writer or reader.
+fn format_error<E>(e: salibc_fmt::Error<E>, io: impl FnOnce(E) -> ErrorStatus) -> ErrorStatus {
+    match e {
+        salibc_fmt::Error::Io(e) => io(e),
+        salibc_fmt::Error::WriteZero | salibc_fmt::Error::CustomFailed => ErrorStatus::Generic,
+        salibc_fmt::Error::InvalidFormat => ErrorStatus::InvalidArgument,
+    }
+}
+
+/// Converts the result of [`crate::format::printf_to`] into what the printf family returns,
+/// that is the amount of bytes written or a negative value on failure.
+fn printf_result<E>(
+    result: Result<usize, salibc_fmt::Error<E>>,
+    io: impl FnOnce(E) -> ErrorStatus,
+) -> c_int {
+    match result {
+        Ok(len) => match c_int::try_from(len) {
+            Ok(len) => len,
+            Err(_) => {
+                set_error(ErrorStatus::StrTooLong);
+                -1
+            }
+        },
+        Err(e) => {
+            set_error(format_error(e, io));
+            -1
+        }
+    }
+}
+
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn printf(fmt: *const c_char, mut args: ...) -> c_int {
-    let fmt = unsafe { CStr::from_ptr(fmt) };
+    unsafe { vprintf(fmt, args.as_va_list()) }
+}
 
-    match crate::format::printf_to(
-        unsafe { &mut **stdout.0.get() },
-        fmt.to_bytes(),
-        args.as_va_list(),
-    ) {
-        Ok(_) => 0,
-        Err(_) => -1,
-    }
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn vprintf(fmt: *const c_char, args: VaList) -> c_int {
+    unsafe { vfprintf(*stdout.0.get(), fmt, args) }
 }
 
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn puts(s: *const c_char) -> c_int {
     unsafe {
-        let stdout_s = &mut **stdout.0.get();
+        let stdout_s = *stdout.0.get();
+        // Both writes land together
+        let _guard = FileGuard::lock(stdout_s);
         let ret = fputs(s, stdout_s);
         if ret != 0 {
             return ret;
@@ -400,11 +1016,12 @@ pub unsafe extern "C" fn vfprintf(
     args: core::ffi::VaList,
 ) -> c_int {
     let fmt = unsafe { CStr::from_ptr(fmt) };
-
-    match crate::format::printf_to(unsafe { &mut *stream }, fmt.to_bytes(), args) {
-        Ok(_) => 0,
-        Err(_) => -1,
-    }
+    let mut stream = unsafe { FileGuard::lock(stream) };
+    // SAFETY: it is up to the caller to pass arguments matching the format
+    let result = unsafe {
+        crate::format::printf_to(&mut *stream, fmt.to_bytes(), args, &RegisteredSpecifiers)
+    };
+    printf_result(result, |e| e)
 }
 
 #[unsafe(no_mangle)]
@@ -451,43 +1068,155 @@ pub unsafe extern "C" fn vsnprintf(
     }
 
     let len = stream.len();
-    match crate::format::printf_to(
-        &mut BufWriter::new(&mut stream[..len.saturating_sub(1)]),
-        fmt_str.to_bytes(),
-        args,
-    ) {
-        Ok(am) => {
-            if len != 0 {
-                stream[am.min(len - 1)] = 0;
+    let mut writer = BufWriter::new(&mut stream[..len.saturating_sub(1)]);
+    // SAFETY: it is up to the caller to pass arguments matching the format
+    let result = unsafe {
+        crate::format::printf_to(&mut writer, fmt_str.to_bytes(), args, &RegisteredSpecifiers)
+    };
+
+    let stored = writer.stored();
+    if len != 0 {
+        stream[stored] = 0;
+    }
+    printf_result(result, |e| match e {})
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn asprintf(
+    strp: *mut *mut c_char,
+    fmt: *const c_char,
+    mut args: ...
+) -> c_int {
+    unsafe { vasprintf(strp, fmt, args.as_va_list()) }
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn vasprintf(
+    strp: *mut *mut c_char,
+    fmt: *const c_char,
+    args: VaList,
+) -> c_int {
+    let fmt = unsafe { CStr::from_ptr(fmt) };
+
+    let mut buf = Vec::new();
+    // SAFETY: it is up to the caller to pass arguments matching the format
+    let result =
+        unsafe { crate::format::printf_to(&mut buf, fmt.to_bytes(), args, &RegisteredSpecifiers) };
+    let result = printf_result(result, |_| ErrorStatus::OutOfMemory);
+    if result < 0 {
+        return result;
+    }
+    let len = buf.len();
+
+    // The result has to be released with `free`
+    let s = malloc(len + 1).cast::<u8>();
+    if s.is_null() {
+        set_error(ErrorStatus::OutOfMemory);
+        return -1;
+    }
+
+    unsafe {
+        core::ptr::copy_nonoverlapping(buf.as_ptr(), s, len);
+        *s.add(len) = 0;
+        *strp = s.cast();
+    }
+    result
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn dprintf(fd: c_int, fmt: *const c_char, mut args: ...) -> c_int {
+    unsafe { vdprintf(fd, fmt, args.as_va_list()) }
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn vdprintf(fd: c_int, fmt: *const c_char, args: VaList) -> c_int {
+    let fmt = unsafe { CStr::from_ptr(fmt) };
+
+    // Formatted as a whole first so that the output is written with as few writes as possible
+    let mut buf = Vec::new();
+    // SAFETY: it is up to the caller to pass arguments matching the format
+    let result =
+        unsafe { crate::format::printf_to(&mut buf, fmt.to_bytes(), args, &RegisteredSpecifiers) };
+    let result = printf_result(result, |_| ErrorStatus::OutOfMemory);
+    if result < 0 {
+        return result;
+    }
+
+    let desc = try_errno!(unistd::get(fd), -1);
+    let mut desc = desc.lock();
+    if desc.flags & O_ACCMODE == O_RDONLY {
+        set_error(ErrorStatus::UnknownResource);
+        return -1;
+    }
+
+    let mut bytes = &buf[..];
+    while !bytes.is_empty() {
+        let wrote = try_errno!(desc.file.write_unbuffered(bytes), -1);
+        if wrote == 0 {
+            set_error(ErrorStatus::Generic);
+            return -1;
+        }
+        bytes = &bytes[wrote..];
+    }
+    result
+}
+
+/// Converts the result of [`crate::parse::scanf_from`] into what the scanf family returns,
+/// that is the amount of conversions assigned or [`EOF`] on an input failure before the first one,
+/// a read error is an input failure too but still sets errno.
+fn scanf_result<E>(
+    result: Result<(usize, Option<usize>), ScanfError<E>>,
+    io: impl FnOnce(E) -> ErrorStatus,
+) -> c_int {
+    match result {
+        Ok((_, Some(assigned))) => assigned as c_int,
+        Ok((_, None)) => EOF,
+        Err(e) => {
+            set_error(format_error(e.error, io));
+            if e.assigned == 0 {
+                EOF
+            } else {
+                e.assigned as c_int
             }
-            am as c_int
         }
-        Err(_) => -1,
     }
 }
 
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn scanf(fmt: *const c_char, mut args: ...) -> c_int {
+    unsafe { vscanf(fmt, args.as_va_list()) }
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn vscanf(fmt: *const c_char, args: VaList) -> c_int {
+    unsafe { vfscanf(*stdin.0.get(), fmt, args) }
+}
+
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn fscanf(stream: *mut File, fmt: *const c_char, mut args: ...) -> c_int {
+    unsafe { vfscanf(stream, fmt, args.as_va_list()) }
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn vfscanf(stream: *mut File, fmt: *const c_char, args: VaList) -> c_int {
     let fmt = unsafe { CStr::from_ptr(fmt) };
-    let stream = unsafe { &mut *stream };
-    try_errno!(
-        crate::parse::scanf_from(stream, fmt.to_bytes(), args.as_va_list()),
-        -1
-    );
-    0
+    let mut stream = unsafe { FileGuard::lock(stream) };
+    // SAFETY: it is up to the caller to pass pointers matching the format
+    let result = unsafe { crate::parse::scanf_from(&mut *stream, fmt.to_bytes(), args) };
+    scanf_result(result, |e| e)
 }
 
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn sscanf(s: *const c_char, fmt: *const c_char, mut args: ...) -> c_int {
+    unsafe { vsscanf(s, fmt, args.as_va_list()) }
+}
+
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn vsscanf(s: *const c_char, fmt: *const c_char, args: VaList) -> c_int {
     let fmt = unsafe { CStr::from_ptr(fmt) };
     let stream = unsafe { core::slice::from_raw_parts(s as *const u8, strlen(s)) };
-    let (_, matched) = try_errno!(
-        crate::parse::scanf_from(
-            &mut BufReader::new(stream),
-            fmt.to_bytes(),
-            args.as_va_list()
-        ),
-        -1
-    );
-    matched as c_int
+    // SAFETY: it is up to the caller to pass pointers matching the format
+    let result =
+        unsafe { crate::parse::scanf_from(&mut BufReader::new(stream), fmt.to_bytes(), args) };
+    scanf_result(result, |e| match e {})
 }
@@ -1,5 +1,6 @@
 use core::ffi::{CStr, c_double, c_long, c_longlong, c_uint, c_ulong, c_ulonglong};
 use core::num::IntErrorKind;
+use core::ops::Range;
 use core::ptr::NonNull;
 use core::{
     ffi::{c_char, c_int, c_void},
@@ -19,7 +20,9 @@ use safa_api::syscalls;
 extern crate alloc;
 
 use crate::errno::set_error;
-use crate::{SyncUnsafeCell, try_errno};
+use crate::fcntl::O_RDWR;
+use crate::file;
+use crate::{SyncUnsafeCell, try_errno, unistd};
 
 unsafe fn cstr_to_bytes<'a>(p: *const c_char) -> &'a [u8] {
     if p.is_null() {
@@ -539,3 +542,60 @@ pub unsafe extern "C" fn bsearch(
 
     core::ptr::null_mut()
 }
+
+//
+// -- temporary files
+//
+
+/// Returns the range of the `XXXXXX` followed by `suffix_len` bytes at the end of `template`.
+fn temp_template_range(template: &[u8], suffix_len: usize) -> Result<Range<usize>, ErrorStatus> {
+    let end = template
+        .len()
+        .checked_sub(suffix_len)
+        .ok_or(ErrorStatus::InvalidArgument)?;
+    let start = end.checked_sub(6).ok_or(ErrorStatus::InvalidArgument)?;
+
+    if template[start..end].iter().any(|c| *c != b'X') {
+        return Err(ErrorStatus::InvalidArgument);
+    }
+    Ok(start..end)
+}
+
+/// Replaces the `XXXXXX` before the last `suffix_len` bytes of `template` to create a new file,
+/// returns the opened file descriptor.
+#[unsafe(no_mangle)]
+pub extern "C" fn mkstemps(template: *mut c_char, suffix_len: c_int) -> c_int {
+    let template = unsafe { CStr::from_ptr(template) };
+    let template = unsafe {
+        core::slice::from_raw_parts_mut(template.as_ptr() as *mut u8, template.count_bytes())
+    };
+
+    let suffix_len = try_errno!(
+        usize::try_from(suffix_len).map_err(|_| ErrorStatus::InvalidArgument),
+        -1
+    );
+    let random = try_errno!(temp_template_range(template, suffix_len), -1);
+
+    let f = try_errno!(file::create_temp_file(template, random), -1);
+    try_errno!(unistd::install(f, O_RDWR), -1)
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn mkstemp(template: *mut c_char) -> c_int {
+    mkstemps(template, 0)
+}
+
+/// Replaces the trailing `XXXXXX` of `template` to create a new directory, returns `template`.
+#[unsafe(no_mangle)]
+pub extern "C" fn mkdtemp(template: *mut c_char) -> *mut c_char {
+    let bytes = unsafe { CStr::from_ptr(template) };
+    let bytes =
+        unsafe { core::slice::from_raw_parts_mut(bytes.as_ptr() as *mut u8, bytes.count_bytes()) };
+
+    let random = try_errno!(temp_template_range(bytes, 0), ptr::null_mut());
+    try_errno!(
+        file::create_temp(bytes, random, syscalls::fs::createdir),
+        ptr::null_mut()
+    );
+    template
+}
@@ -0,0 +1,264 @@
+//! File descriptors on top of `safa_api` resources, see `include/unistd.h`.
+
+use core::{
+    ffi::{c_int, c_void},
+    fmt::{self, Debug},
+};
+
+use alloc::{sync::Arc, vec::Vec};
+use safa_api::{
+    errors::ErrorStatus,
+    sync::locks::Mutex,
+    syscalls::{resources, types::Ri},
+};
+
+use crate::{
+    errno::set_error,
+    fcntl::{O_ACCMODE, O_APPEND, O_RDONLY, O_RDWR, O_WRONLY},
+    file::{FileBackend, FileUnbuffered, SeekPosition},
+    stdio::{OffT, seek_position},
+    try_errno,
+};
+
+/// The amount of descriptors a process can have open, see `include/limits.h`.
+pub const OPEN_MAX: usize = 255;
+
+/// An open file shared by the descriptors duplicated from the same descriptor.
+#[derive(Debug)]
+pub(crate) struct OpenFile {
+    pub(crate) file: FileUnbuffered,
+    /// The access mode and file status flags, see [`crate::fcntl::fcntl`]
+    pub(crate) flags: c_int,
+}
+
+impl Drop for OpenFile {
+    fn drop(&mut self) {
+        _ = resources::destroy(self.file.resource());
+    }
+}
+
+pub(crate) type Description = Arc<Mutex<OpenFile>>;
+
+/// Creates the open file of `file`, `flags` are the access mode and file status flags it was opened with.
+pub(crate) fn describe(file: FileUnbuffered, flags: c_int) -> Description {
+    Arc::new(Mutex::new(OpenFile { file, flags }))
+}
+
+/// The open descriptors indexed by their number, all below [`OPEN_MAX`].
+static DESCRIPTORS: Mutex<Vec<Option<Description>>> = Mutex::new(Vec::new());
+
+/// Installs `desc` at the lowest free descriptor that is at least `min`, returns the descriptor.
+///
+/// Fails with [`ErrorStatus::InvalidArgument`] (`EINVAL`) if `min` isn't below [`OPEN_MAX`]
+/// and with [`ErrorStatus::OutOfMemory`] (`EMFILE`) if every descriptor from `min` on is open.
+pub(crate) fn install_from(desc: Description, min: usize) -> Result<c_int, ErrorStatus> {
+    if min >= OPEN_MAX {
+        return Err(ErrorStatus::InvalidArgument);
+    }
+
+    let mut descriptors = DESCRIPTORS.lock();
+    let fd = (min..OPEN_MAX)
+        .find(|fd| descriptors.get(*fd).is_none_or(Option::is_none))
+        .ok_or(ErrorStatus::OutOfMemory)?;
+
+    if fd >= descriptors.len() {
+        descriptors.resize(fd + 1, None);
+    }
+    descriptors[fd] = Some(desc);
+    Ok(fd as c_int)
+}
+
+/// Installs `desc` at `fd`, returns the description previously at `fd` if any.
+///
+/// Fails with [`ErrorStatus::UnknownResource`] (`EBADF`) if `fd` isn't below [`OPEN_MAX`].
+pub(crate) fn install_at(desc: Description, fd: usize) -> Result<Option<Description>, ErrorStatus> {
+    if fd >= OPEN_MAX {
+        return Err(ErrorStatus::UnknownResource);
+    }
+
+    let mut descriptors = DESCRIPTORS.lock();
+    if fd >= descriptors.len() {
+        descriptors.resize(fd + 1, None);
+    }
+    Ok(descriptors[fd].replace(desc))
+}
+
+/// Gives `file` the lowest free descriptor, `flags` are the access mode and file status flags it was opened with.
+pub(crate) fn install(file: FileUnbuffered, flags: c_int) -> Result<c_int, ErrorStatus> {
+    install_from(describe(file, flags), 0)
+}
+
+/// Gives the standard stream `ri` the descriptor `fd`, returns its open file.
+pub(crate) fn install_std(fd: c_int, ri: Ri) -> Description {
+    let desc = describe(FileUnbuffered::new(ri), O_RDWR);
+    _ = install_at(desc.clone(), fd as usize);
+    desc
+}
+
+/// A [`FileBackend`] over an open file, sharing its position with the descriptors referring to it,
+/// used by the streams that have a descriptor.
+pub(crate) struct DescriptionFile(Option<Description>);
+
+impl Debug for DescriptionFile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Not locking the open file, which the caller may hold
+        f.debug_tuple("DescriptionFile").finish_non_exhaustive()
+    }
+}
+
+impl DescriptionFile {
+    pub(crate) const fn new(desc: Description) -> Self {
+        Self(Some(desc))
+    }
+
+    fn desc(&self) -> Result<&Description, ErrorStatus> {
+        self.0.as_ref().ok_or(ErrorStatus::UnknownResource)
+    }
+}
+
+impl FileBackend for DescriptionFile {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, ErrorStatus> {
+        self.desc()?.lock().file.read_unbuffered(buf)
+    }
+
+    fn write(&mut self, bytes: &[u8]) -> Result<usize, ErrorStatus> {
+        self.desc()?.lock().file.write_unbuffered(bytes)
+    }
+
+    fn seek(&mut self, wrench: SeekPosition) -> Result<(), ErrorStatus> {
+        self.desc()?.lock().file.seek(wrench);
+        Ok(())
+    }
+
+    fn offset(&mut self) -> Result<usize, ErrorStatus> {
+        self.desc()?.lock().file.offset()
+    }
+
+    fn size(&mut self) -> Result<usize, ErrorStatus> {
+        self.desc()?.lock().file.size()
+    }
+
+    /// Changes `O_APPEND` for every descriptor referring to the open file, as `fcntl` would.
+    fn set_append(&mut self, append: bool) -> Result<(), ErrorStatus> {
+        let desc = self.desc()?;
+        let mut desc = desc.lock();
+        desc.file.set_append(append)?;
+        desc.flags = if append {
+            desc.flags | O_APPEND
+        } else {
+            desc.flags & !O_APPEND
+        };
+        Ok(())
+    }
+
+    fn sync(&mut self) -> Result<(), ErrorStatus> {
+        self.desc()?.lock().file.sync()
+    }
+
+    fn truncate(&mut self, len: usize) -> Result<(), ErrorStatus> {
+        self.desc()?.lock().file.truncate(len)
+    }
+
+    /// The resource is destroyed once no descriptor refers to the open file anymore either.
+    fn close(&mut self) -> Result<(), ErrorStatus> {
+        self.0.take();
+        Ok(())
+    }
+
+    fn resource(&self) -> Option<Ri> {
+        self.0.as_ref().map(|desc| desc.lock().file.resource())
+    }
+}
+
+/// Returns the open file `fd` refers to, fails with [`ErrorStatus::UnknownResource`] (`EBADF`) if `fd` isn't open.
+pub(crate) fn get(fd: c_int) -> Result<Description, ErrorStatus> {
+    let fd = usize::try_from(fd).map_err(|_| ErrorStatus::UnknownResource)?;
+    DESCRIPTORS
+        .lock()
+        .get(fd)
+        .cloned()
+        .flatten()
+        .ok_or(ErrorStatus::UnknownResource)
+}
+
+/// Closes `fd`, the underlying resource is destroyed once no descriptor refers to it anymore.
+pub(crate) fn close_fd(fd: c_int) -> Result<(), ErrorStatus> {
+    let fd = usize::try_from(fd).map_err(|_| ErrorStatus::UnknownResource)?;
+    let desc = DESCRIPTORS
+        .lock()
+        .get_mut(fd)
+        .and_then(Option::take)
+        .ok_or(ErrorStatus::UnknownResource)?;
+
+    // Dropped outside of the lock as it may destroy the resource
+    drop(desc);
+    Ok(())
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn read(fd: c_int, buf: *mut c_void, count: usize) -> isize {
+    let desc = try_errno!(get(fd), -1);
+    let mut desc = desc.lock();
+    if desc.flags & O_ACCMODE == O_WRONLY {
+        set_error(ErrorStatus::UnknownResource);
+        return -1;
+    }
+
+    let buf = unsafe { core::slice::from_raw_parts_mut(buf.cast::<u8>(), count) };
+    try_errno!(desc.file.read_unbuffered(buf), -1) as isize
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn write(fd: c_int, buf: *const c_void, count: usize) -> isize {
+    let desc = try_errno!(get(fd), -1);
+    let mut desc = desc.lock();
+    if desc.flags & O_ACCMODE == O_RDONLY {
+        set_error(ErrorStatus::UnknownResource);
+        return -1;
+    }
+
+    let buf = unsafe { core::slice::from_raw_parts(buf.cast::<u8>(), count) };
+    try_errno!(desc.file.write_unbuffered(buf), -1) as isize
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn close(fd: c_int) -> c_int {
+    try_errno!(close_fd(fd), -1);
+    0
+}
+
+/// Changes the position of `fd` to `offset` relative to `whence`, returns the resulting position.
+#[unsafe(no_mangle)]
+pub extern "C" fn lseek(fd: c_int, offset: OffT, whence: c_int) -> OffT {
+    let desc = try_errno!(get(fd), -1);
+    let mut desc = desc.lock();
+
+    let file = &mut desc.file;
+    let current = try_errno!(file.offset(), -1);
+    let pos = try_errno!(seek_position(offset, whence, current, || file.size()), -1);
+    file.seek(pos);
+    try_errno!(file.offset(), -1) as OffT
+}
+
+/// Returns a new descriptor referring to the same open file as `fd`.
+#[unsafe(no_mangle)]
+pub extern "C" fn dup(fd: c_int) -> c_int {
+    let desc = try_errno!(get(fd), -1);
+    try_errno!(install_from(desc, 0), -1)
+}
+
+/// Makes `new_fd` refer to the same open file as `old_fd`, closing `new_fd` first if it was open.
+#[unsafe(no_mangle)]
+pub extern "C" fn dup2(old_fd: c_int, new_fd: c_int) -> c_int {
+    let desc = try_errno!(get(old_fd), -1);
+    let Ok(new) = usize::try_from(new_fd) else {
+        set_error(ErrorStatus::UnknownResource);
+        return -1;
+    };
+
+    if old_fd != new_fd {
+        // Dropped outside of the lock as it may destroy the resource
+        drop(try_errno!(install_at(desc, new), -1));
+    }
+    new_fd
+}
@@ -0,0 +1,20 @@
+#!/bin/bash
+# Builds the C tests in `tests` against the libc
+# output is in the `out/tests` directory, run them on SafaOS, each one exits with 0 if it passes
+
+target=$1
+if [ -z "$target" ]; then
+    echo "Usage: $0 [arch=x86_64|aarch64]"
+    exit 1
+fi
+
+set -euo pipefail
+
+./build.sh "$target"
+
+mkdir -p out/tests
+for test in tests/*.c; do
+    name=$(basename "$test" .c)
+    ${CC:-clang} --target="$target-unknown-none" -nostdlib -nostdlibinc -isystem include -static -fuse-ld=lld \
+        out/crt0.o "$test" out/libc.a -o "out/tests/$name"
+done
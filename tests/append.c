@@ -0,0 +1,58 @@
+#include <assert.h>
+#include <fcntl.h>
+#include <stdio.h>
+#include <string.h>
+#include <unistd.h>
+
+int main() {
+    FILE *f = tmpfile();
+    assert(f);
+    assert(fputs("hello", f) >= 0);
+    assert(fflush(f) == 0);
+
+    int fd = fileno(f);
+    assert(fd >= 0);
+    int flags = fcntl(fd, F_GETFL);
+    assert(flags >= 0);
+    assert(!(flags & O_APPEND));
+
+    // Appending writes at the end of the file wherever the descriptor is
+    assert(lseek(fd, 0, SEEK_SET) == 0);
+    assert(fcntl(fd, F_SETFL, flags | O_APPEND) == 0);
+    assert(fcntl(fd, F_GETFL) & O_APPEND);
+    assert(write(fd, "!", 1) == 1);
+    assert(lseek(fd, 0, SEEK_CUR) == 6);
+
+    // Once appending stops writes land at the position again and reads move it forward
+    assert(fcntl(fd, F_SETFL, flags) == 0);
+    assert(!(fcntl(fd, F_GETFL) & O_APPEND));
+    assert(lseek(fd, 0, SEEK_CUR) == 6);
+    assert(lseek(fd, 0, SEEK_SET) == 0);
+    assert(write(fd, "J", 1) == 1);
+    assert(lseek(fd, 0, SEEK_CUR) == 1);
+
+    char buf[16] = {0};
+    assert(read(fd, buf, sizeof(buf)) == 5);
+    assert(memcmp(buf, "ello!", 5) == 0);
+    assert(read(fd, buf, sizeof(buf)) == 0);
+    assert(fclose(f) == 0);
+
+    // The `a` modes of `fopen` append too
+    char path[L_tmpnam];
+    assert(tmpnam(path));
+    f = fopen(path, "w");
+    assert(f);
+    assert(fputs("ab", f) >= 0);
+    assert(fclose(f) == 0);
+
+    f = fopen(path, "a+");
+    assert(f);
+    assert(fseek(f, 0, SEEK_SET) == 0);
+    assert(fputc('c', f) == 'c');
+    rewind(f);
+    assert(fread(buf, 1, sizeof(buf), f) == 3);
+    assert(memcmp(buf, "abc", 3) == 0);
+    assert(fclose(f) == 0);
+    assert(remove(path) == 0);
+    return 0;
+}
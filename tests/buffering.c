@@ -0,0 +1,80 @@
+#include <assert.h>
+#include <stdio.h>
+#include <string.h>
+
+/* Reads from a string, recording how often and how much it was asked for */
+struct source {
+    const char *data;
+    size_t pos;
+    int calls;
+    size_t last_size;
+};
+
+static ssize_t source_read(void *cookie, char *buf, size_t size) {
+    struct source *src = cookie;
+    src->calls++;
+    src->last_size = size;
+
+    size_t left = strlen(src->data) - src->pos;
+    size_t len = size < left ? size : left;
+    memcpy(buf, src->data + src->pos, len);
+    src->pos += len;
+    return len;
+}
+
+int main() {
+    cookie_io_functions_t io = {.read = source_read};
+
+    // A fully buffered stream reads ahead and serves the following reads from its buffer
+    struct source src = {.data = "hello world"};
+    FILE *f = fopencookie(&src, "r", io);
+    assert(f);
+    assert(setvbuf(f, NULL, _IOFBF, BUFSIZ) == 0);
+    assert(fgetc(f) == 'h');
+    assert(src.calls == 1);
+    assert(src.pos == 11);
+    char buf[8192] = {0};
+    assert(fread(buf, 1, 4, f) == 4);
+    assert(memcmp(buf, "ello", 4) == 0);
+    assert(src.calls == 1);
+    assert(fclose(f) == 0);
+
+    // Reads larger than the buffer go to the backend directly
+    src = (struct source){.data = "abc"};
+    f = fopencookie(&src, "r", io);
+    assert(f);
+    assert(setvbuf(f, NULL, _IOFBF, 1024) == 0);
+    assert(fread(buf, 1, sizeof(buf), f) == 3);
+    assert(src.last_size >= sizeof(buf));
+    assert(memcmp(buf, "abc", 3) == 0);
+    assert(fclose(f) == 0);
+
+    // A line buffered stream never reads past the end of a line
+    src = (struct source){.data = "ab\ncd\n"};
+    f = fopencookie(&src, "r", io);
+    assert(f);
+    assert(setvbuf(f, NULL, _IOLBF, 0) == 0);
+    assert(fgetc(f) == 'a');
+    assert(src.pos == 3);
+    assert(fgets(buf, sizeof(buf), f) == buf);
+    assert(strcmp(buf, "b\n") == 0);
+    assert(src.pos == 3);
+    assert(fgets(buf, sizeof(buf), f) == buf);
+    assert(strcmp(buf, "cd\n") == 0);
+    assert(src.pos == 6);
+    assert(fclose(f) == 0);
+
+    // Writing after reading lands where the reader is, not where the buffer read up to
+    f = tmpfile();
+    assert(f);
+    assert(fputs("0123456789", f) >= 0);
+    rewind(f);
+    assert(fgetc(f) == '0');
+    assert(fputc('X', f) == 'X');
+    assert(ftell(f) == 2);
+    rewind(f);
+    assert(fread(buf, 1, 10, f) == 10);
+    assert(memcmp(buf, "0X23456789", 10) == 0);
+    assert(fclose(f) == 0);
+    return 0;
+}
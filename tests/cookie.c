@@ -0,0 +1,118 @@
+#include <assert.h>
+#include <errno.h>
+#include <stdio.h>
+#include <string.h>
+
+/* A file in memory, closing it records that it was closed */
+struct mem {
+    char data[64];
+    size_t len;
+    size_t pos;
+    int closed;
+};
+
+static ssize_t mem_read(void *cookie, char *buf, size_t size) {
+    struct mem *mem = cookie;
+    size_t left = mem->len - mem->pos;
+    size_t len = size < left ? size : left;
+    memcpy(buf, mem->data + mem->pos, len);
+    mem->pos += len;
+    return len;
+}
+
+static ssize_t mem_write(void *cookie, const char *buf, size_t size) {
+    struct mem *mem = cookie;
+    size_t room = sizeof(mem->data) - mem->pos;
+    size_t len = size < room ? size : room;
+    memcpy(mem->data + mem->pos, buf, len);
+    mem->pos += len;
+    if (mem->pos > mem->len)
+        mem->len = mem->pos;
+    return len;
+}
+
+static int mem_seek(void *cookie, off_t *offset, int whence) {
+    struct mem *mem = cookie;
+    off_t base = whence == SEEK_SET ? 0 : whence == SEEK_CUR ? (off_t)mem->pos : (off_t)mem->len;
+    if (base + *offset < 0 || base + *offset > (off_t)sizeof(mem->data))
+        return -1;
+    mem->pos = base + *offset;
+    *offset = mem->pos;
+    return 0;
+}
+
+static int mem_close(void *cookie) {
+    struct mem *mem = cookie;
+    mem->closed++;
+    return 0;
+}
+
+static int fun_read(void *cookie, char *buf, int size) { return mem_read(cookie, buf, size); }
+
+static int fun_write(void *cookie, const char *buf, int size) { return mem_write(cookie, buf, size); }
+
+static off_t fun_seek(void *cookie, off_t offset, int whence) {
+    return mem_seek(cookie, &offset, whence) == 0 ? offset : -1;
+}
+
+int main() {
+    cookie_io_functions_t io = {mem_read, mem_write, mem_seek, mem_close};
+
+    struct mem mem = {0};
+    FILE *f = fopencookie(&mem, "w+", io);
+    assert(f);
+    assert(fprintf(f, "hello %d", 7) == 7);
+    assert(ftell(f) == 7);
+    assert(fseek(f, 0, SEEK_SET) == 0);
+    assert(mem.len == 7);
+    assert(fgetc(f) == 'h');
+    assert(fseek(f, -1, SEEK_END) == 0);
+    assert(fgetc(f) == '7');
+    assert(fgetc(f) == EOF);
+    assert(mem.closed == 0);
+    assert(fclose(f) == 0);
+    assert(mem.closed == 1);
+    assert(memcmp(mem.data, "hello 7", 7) == 0);
+
+    // Appending seeks to the end through the seek callback before each write
+    mem.pos = 0;
+    f = fopencookie(&mem, "a", io);
+    assert(f);
+    assert(fputs("!", f) >= 0);
+    assert(fclose(f) == 0);
+    assert(mem.len == 8);
+    assert(memcmp(mem.data, "hello 7!", 8) == 0);
+
+    // Missing callbacks read nothing, discard writes and fail to seek
+    f = fopencookie(&mem, "r+", (cookie_io_functions_t){0});
+    assert(f);
+    assert(fgetc(f) == EOF);
+    assert(feof(f));
+    assert(fputs("discarded", f) >= 0);
+    assert(fflush(f) == 0);
+    assert(fseek(f, 0, SEEK_SET) == -1);
+    assert(fclose(f) == 0);
+    assert(mem.len == 8);
+
+    // funopen streams are readable and writable depending on the callbacks given
+    mem = (struct mem){.data = "abc", .len = 3};
+    f = funopen(&mem, fun_read, NULL, fun_seek, mem_close);
+    assert(f);
+    assert(fgetc(f) == 'a');
+    assert(fseek(f, 2, SEEK_SET) == 0);
+    assert(fgetc(f) == 'c');
+    assert(fputc('x', f) == EOF || fflush(f) == EOF);
+    assert(fclose(f) == 0);
+    assert(mem.closed == 1);
+
+    f = funopen(&mem, NULL, fun_write, NULL, NULL);
+    assert(f);
+    assert(fputs("de", f) >= 0);
+    assert(fclose(f) == 0);
+    assert(memcmp(mem.data, "abcde", 5) == 0);
+
+    errno = 0;
+    assert(funopen(&mem, NULL, NULL, fun_seek, mem_close) == NULL);
+    assert(errno == EINVAL);
+    return 0;
+}
@@ -0,0 +1,45 @@
+#include <assert.h>
+#include <errno.h>
+#include <fcntl.h>
+#include <stdio.h>
+#include <unistd.h>
+
+int main() {
+    char path[L_tmpnam];
+    assert(tmpnam(path));
+    FILE *f = fopen(path, "w");
+    assert(f);
+    assert(fputs("abc", f) >= 0);
+    assert(fclose(f) == 0);
+
+    // A stream can't be given access its descriptor doesn't have
+    int fd = open(path, O_RDONLY);
+    assert(fd >= 0);
+    errno = 0;
+    assert(!fdopen(fd, "w"));
+    assert(errno == EINVAL);
+    assert(!fdopen(fd, "r+"));
+    assert(!fdopen(fd, "a"));
+
+    f = fdopen(fd, "r");
+    assert(f);
+    assert(fgetc(f) == 'a');
+    assert(fclose(f) == 0);
+
+    fd = open(path, O_WRONLY);
+    assert(fd >= 0);
+    assert(!fdopen(fd, "r"));
+    assert(!fdopen(fd, "w+"));
+    f = fdopen(fd, "a");
+    assert(f);
+    assert(fputc('d', f) == 'd');
+    assert(fclose(f) == 0);
+
+    f = fopen(path, "r");
+    assert(f);
+    char buf[8] = {0};
+    assert(fread(buf, 1, sizeof(buf), f) == 4);
+    assert(fclose(f) == 0);
+    assert(remove(path) == 0);
+    return 0;
+}
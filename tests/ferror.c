@@ -0,0 +1,55 @@
+#include <assert.h>
+#include <errno.h>
+#include <stdio.h>
+
+static ssize_t failing_read(void *cookie, char *buf, size_t size) {
+    errno = EPIPE;
+    return -1;
+}
+
+static ssize_t failing_write(void *cookie, const char *buf, size_t size) {
+    errno = EPIPE;
+    return -1;
+}
+
+int main() {
+    // Reaching the end of the file sets the end of file indicator only
+    FILE *f = tmpfile();
+    assert(f);
+    assert(fputc('a', f) == 'a');
+    rewind(f);
+    assert(fgetc(f) == 'a');
+    assert(fgetc(f) == EOF);
+    assert(feof(f));
+    assert(!ferror(f));
+    clearerr(f);
+    assert(!feof(f));
+    assert(fclose(f) == 0);
+
+    // A failing read sets the error indicator, which stays set until cleared
+    cookie_io_functions_t io = {.read = failing_read, .write = failing_write};
+    f = fopencookie(NULL, "r+", io);
+    assert(f);
+    assert(fgetc(f) == EOF);
+    assert(ferror(f));
+    assert(!feof(f));
+    assert(fseek(f, 0, SEEK_SET) == -1);
+    assert(ferror(f));
+    clearerr(f);
+    assert(!ferror(f));
+
+    // So does a failing write, whether it fails right away or once flushed
+    assert(setvbuf(f, NULL, _IONBF, 0) == 0);
+    assert(fputc('a', f) == EOF);
+    assert(ferror(f));
+    clearerr(f);
+    assert(setvbuf(f, NULL, _IOFBF, BUFSIZ) == 0);
+    assert(fputs("buffered", f) >= 0);
+    assert(!ferror(f));
+    assert(fflush(f) == EOF);
+    assert(ferror(f));
+    clearerr(f);
+    assert(!ferror(f) && !feof(f));
+    fclose(f);
+    return 0;
+}
@@ -0,0 +1,51 @@
+#include <assert.h>
+#include <pthread.h>
+#include <stdint.h>
+#include <stdio.h>
+#include <string.h>
+
+static FILE *shared;
+
+static void *try_lock(void *arg) {
+    int locked = ftrylockfile(shared) == 0;
+    if (locked)
+        funlockfile(shared);
+    return (void *)(intptr_t)locked;
+}
+
+static int locked_by_other_thread(void) {
+    pthread_t thread;
+    void *locked;
+    assert(pthread_create(&thread, NULL, try_lock, NULL) == 0);
+    assert(pthread_join(thread, &locked) == 0);
+    return !(intptr_t)locked;
+}
+
+int main() {
+    shared = tmpfile();
+    assert(shared);
+
+    // The thread holding a stream can lock it again, other threads can't until it is unlocked as often
+    flockfile(shared);
+    assert(ftrylockfile(shared) == 0);
+    assert(locked_by_other_thread());
+
+    assert(putc_unlocked('a', shared) == 'a');
+    assert(fwrite_unlocked("bc\n", 1, 3, shared) == 3);
+    funlockfile(shared);
+    assert(locked_by_other_thread());
+    funlockfile(shared);
+    assert(!locked_by_other_thread());
+
+    // The unlocked functions read through the same buffer while the stream is held
+    rewind(shared);
+    char line[8] = {0};
+    flockfile(shared);
+    assert(getc_unlocked(shared) == 'a');
+    assert(fgets_unlocked(line, sizeof(line), shared) == line);
+    funlockfile(shared);
+    assert(strcmp(line, "bc\n") == 0);
+    assert(fgetc(shared) == EOF);
+    assert(fclose(shared) == 0);
+    return 0;
+}
@@ -0,0 +1,79 @@
+#include <assert.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <unistd.h>
+
+/* Collects everything written to it */
+struct sink {
+    char data[64];
+    size_t len;
+};
+
+static ssize_t sink_write(void *cookie, const char *buf, size_t size) {
+    struct sink *sink = cookie;
+    memcpy(sink->data + sink->len, buf, size);
+    sink->len += size;
+    return size;
+}
+
+static ssize_t failing_write(void *cookie, const char *buf, size_t size) { return -1; }
+
+static struct sink at_exit;
+static FILE *at_exit_stream;
+
+static void write_at_exit(void) {
+    // Streams are still open while the atexit handlers run
+    assert(fputs(" handler", at_exit_stream) >= 0);
+}
+
+/* Called when exit closes the stream, the test only passes if by then everything was written */
+static int check_at_exit(void *cookie) {
+    struct sink *sink = cookie;
+    _exit(sink->len == 12 && memcmp(sink->data, "main handler", 12) == 0 ? 0 : 1);
+    return 0;
+}
+
+int main() {
+    cookie_io_functions_t io = {.write = sink_write};
+
+    // fflush(NULL) flushes every open stream
+    struct sink first = {0}, second = {0};
+    FILE *a = fopencookie(&first, "w", io);
+    FILE *b = fopencookie(&second, "w", io);
+    assert(a && b);
+    assert(fputs("a", a) >= 0);
+    assert(fputs("b", b) >= 0);
+    assert(first.len == 0 && second.len == 0);
+    assert(fflush(NULL) == 0);
+    assert(first.len == 1 && second.len == 1);
+
+    // One failing stream fails fflush(NULL) without keeping the others from being flushed
+    cookie_io_functions_t failing = {.write = failing_write};
+    FILE *c = fopencookie(NULL, "w", failing);
+    assert(c);
+    assert(fputs("c", c) >= 0);
+    assert(fputs("aa", a) >= 0);
+    assert(fputs("bb", b) >= 0);
+    assert(fflush(NULL) == EOF);
+    assert(first.len == 3 && second.len == 3);
+    assert(ferror(c));
+
+    // Closed streams are no longer flushed
+    assert(fclose(c) == 0);
+    assert(fclose(b) == 0);
+    assert(fputs("!", a) >= 0);
+    assert(fflush(NULL) == 0);
+    assert(first.len == 4);
+    assert(fclose(a) == 0);
+
+    // What is left buffered is written by exit once the atexit handlers ran
+    cookie_io_functions_t checked = {.write = sink_write, .close = check_at_exit};
+    at_exit_stream = fopencookie(&at_exit, "w", checked);
+    assert(at_exit_stream);
+    assert(fputs("main", at_exit_stream) >= 0);
+    assert(atexit(write_at_exit) == 0);
+    assert(at_exit.len == 0);
+    // Only passes if `check_at_exit` gets to run
+    return 1;
+}
@@ -0,0 +1,81 @@
+#include <assert.h>
+#include <errno.h>
+#include <stdio.h>
+#include <string.h>
+
+static void check_contents(const char *path, const char *expected) {
+    char buf[32] = {0};
+    FILE *f = fopen(path, "rb");
+    assert(f);
+    assert(fread(buf, 1, sizeof(buf), f) == strlen(expected));
+    assert(memcmp(buf, expected, strlen(expected)) == 0);
+    assert(fclose(f) == 0);
+}
+
+int main() {
+    char path[L_tmpnam];
+    assert(tmpnam(path));
+    assert(fopen(path, "r") == NULL);
+    assert(fopen(path, "r+") == NULL);
+
+    // `w` creates the file and can't read
+    FILE *f = fopen(path, "w");
+    assert(f);
+    assert(fputs("hello", f) >= 0);
+    assert(fgetc(f) == EOF);
+    assert(ferror(f));
+    assert(fclose(f) == 0);
+    check_contents(path, "hello");
+
+    // `r` can't write, buffered writes fail once they are flushed
+    f = fopen(path, "r");
+    assert(f);
+    assert(fputc('x', f) == EOF || fflush(f) == EOF);
+    assert(ferror(f));
+    assert(fclose(f) == 0);
+
+    // `r+` keeps the contents and writes from the start
+    f = fopen(path, "r+");
+    assert(f);
+    assert(fputc('J', f) == 'J');
+    assert(fclose(f) == 0);
+    check_contents(path, "Jello");
+
+    // `a` writes at the end wherever the stream was moved to
+    f = fopen(path, "ab");
+    assert(f);
+    assert(fseek(f, 0, SEEK_SET) == 0);
+    assert(fputs("!", f) >= 0);
+    assert(fclose(f) == 0);
+    check_contents(path, "Jello!");
+
+    // `w+` truncates and can read back what it wrote
+    f = fopen(path, "w+b");
+    assert(f);
+    assert(fgetc(f) == EOF);
+    assert(fputs("ab", f) >= 0);
+    rewind(f);
+    assert(fgetc(f) == 'a');
+    assert(fclose(f) == 0);
+    check_contents(path, "ab");
+
+    // `x` only opens files it creates
+    errno = 0;
+    assert(fopen(path, "wx") == NULL);
+    assert(errno == EEXIST);
+    assert(remove(path) == 0);
+    f = fopen(path, "w+x");
+    assert(f);
+    assert(fclose(f) == 0);
+    check_contents(path, "");
+
+    const char *invalid[] = {"", "z", "rx", "ax", "r+q", "+r"};
+    for (size_t i = 0; i < sizeof(invalid) / sizeof(*invalid); i++) {
+        errno = 0;
+        assert(fopen(path, invalid[i]) == NULL);
+        assert(errno == EINVAL);
+    }
+
+    assert(remove(path) == 0);
+    return 0;
+}
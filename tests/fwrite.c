@@ -0,0 +1,47 @@
+#include <assert.h>
+#include <errno.h>
+#include <stdint.h>
+#include <stdio.h>
+#include <string.h>
+
+/* Accepts at most `*cookie` bytes in total, then fails */
+static ssize_t limited_write(void *cookie, const char *buf, size_t size) {
+    size_t *left = cookie;
+    if (*left == 0)
+        return -1;
+
+    size_t len = size < *left ? size : *left;
+    *left -= len;
+    return len;
+}
+
+int main() {
+    FILE *f = tmpfile();
+    assert(f);
+
+    // Nothing is written for zero sized items or zero items
+    assert(fwrite("abc", 0, 3, f) == 0);
+    assert(fwrite("abc", 3, 0, f) == 0);
+    assert(ftell(f) == 0);
+    assert(!ferror(f));
+
+    errno = 0;
+    assert(fwrite("abc", SIZE_MAX, 2, f) == 0);
+    assert(errno == EINVAL);
+    assert(ftell(f) == 0);
+
+    assert(fwrite("abcdef", 2, 3, f) == 3);
+    assert(ftell(f) == 6);
+    assert(fclose(f) == 0);
+
+    // A failing write returns the amount of complete items written and sets the error indicator
+    size_t left = 3;
+    cookie_io_functions_t io = {.write = limited_write};
+    f = fopencookie(&left, "w", io);
+    assert(f);
+    assert(setvbuf(f, NULL, _IONBF, 0) == 0);
+    assert(fwrite("abcdef", 2, 3, f) == 1);
+    assert(ferror(f));
+    assert(fclose(f) == 0);
+    return 0;
+}
@@ -0,0 +1,53 @@
+#include <assert.h>
+#include <errno.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+int main() {
+    char text[] = "one\ntwo\n\nlast";
+    FILE *f = fmemopen(text, strlen(text), "r");
+    assert(f);
+
+    // A null line is allocated and then reused
+    char *line = NULL;
+    size_t n = 0;
+    assert(getline(&line, &n, f) == 4);
+    assert(line && n > 4);
+    assert(strcmp(line, "one\n") == 0);
+    assert(getline(&line, &n, f) == 4);
+    assert(strcmp(line, "two\n") == 0);
+    assert(getline(&line, &n, f) == 1);
+    assert(strcmp(line, "\n") == 0);
+
+    // The last line may end without a delimiter
+    assert(getline(&line, &n, f) == 4);
+    assert(strcmp(line, "last") == 0);
+    assert(getline(&line, &n, f) == -1);
+    assert(feof(f));
+    assert(fclose(f) == 0);
+
+    // A line longer than the buffer grows it
+    f = tmpfile();
+    assert(f);
+    for (int i = 0; i < 300; i++)
+        assert(fputc('x', f) == 'x');
+    assert(fputs(",y", f) >= 0);
+    rewind(f);
+    char *small = malloc(2);
+    assert(small);
+    size_t small_n = 2;
+    assert(getdelim(&small, &small_n, ',', f) == 301);
+    assert(small_n > 301);
+    assert(small[299] == 'x' && small[300] == ',' && small[301] == '\0');
+    assert(getdelim(&small, &small_n, ',', f) == 1);
+    assert(strcmp(small, "y") == 0);
+    assert(fclose(f) == 0);
+    free(small);
+    free(line);
+
+    errno = 0;
+    assert(getline(NULL, &n, stdin) == -1);
+    assert(errno == EINVAL);
+    return 0;
+}
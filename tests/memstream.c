@@ -0,0 +1,81 @@
+#include <assert.h>
+#include <errno.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+int main() {
+    // Reads stop at the size of the buffer
+    char text[] = "hello world";
+    char word[16] = {0};
+    FILE *f = fmemopen(text, strlen(text), "r");
+    assert(f);
+    assert(fscanf(f, "%s", word) == 1);
+    assert(strcmp(word, "hello") == 0);
+    assert(ftell(f) == 5);
+    assert(fseek(f, 0, SEEK_END) == 0);
+    assert(ftell(f) == 11);
+    assert(fgetc(f) == EOF);
+    assert(feof(f));
+    assert(fclose(f) == 0);
+
+    // Writes stay NUL terminated while there is room and fail once the buffer is full
+    char buf[8];
+    memset(buf, 'z', sizeof(buf));
+    f = fmemopen(buf, sizeof(buf), "w");
+    assert(f);
+    assert(fputs("abc", f) >= 0);
+    assert(fflush(f) == 0);
+    assert(strcmp(buf, "abc") == 0);
+    assert(setvbuf(f, NULL, _IONBF, 0) == 0);
+    assert(fwrite("defghij", 1, 7, f) == 5);
+    assert(ferror(f));
+    assert(memcmp(buf, "abcdefgh", 8) == 0);
+    assert(fclose(f) == 0);
+
+    // Appending starts at the first NUL
+    strcpy(buf, "ab");
+    f = fmemopen(buf, sizeof(buf), "a");
+    assert(f);
+    assert(ftell(f) == 2);
+    assert(fputc('c', f) == 'c');
+    assert(fclose(f) == 0);
+    assert(strcmp(buf, "abc") == 0);
+
+    // Without a buffer one is allocated for the stream
+    f = fmemopen(NULL, 16, "w+");
+    assert(f);
+    assert(fprintf(f, "%d", 1234) == 4);
+    rewind(f);
+    int n = 0;
+    assert(fscanf(f, "%d", &n) == 1);
+    assert(n == 1234);
+    assert(fclose(f) == 0);
+
+    errno = 0;
+    assert(fmemopen(buf, 0, "r") == NULL);
+    assert(errno == EINVAL);
+
+    // open_memstream publishes its buffer on each flush and on close
+    char *out = NULL;
+    size_t size = 0;
+    f = open_memstream(&out, &size);
+    assert(f);
+    assert(fprintf(f, "%d-%s", 42, "x") == 4);
+    assert(fflush(f) == 0);
+    assert(size == 4);
+    assert(strcmp(out, "42-x") == 0);
+
+    // Writing past the end fills the gap with zeros
+    assert(fseek(f, 6, SEEK_SET) == 0);
+    assert(fputc('y', f) == 'y');
+    assert(fclose(f) == 0);
+    assert(size == 7);
+    assert(memcmp(out, "42-x\0\0y", 8) == 0);
+    free(out);
+
+    errno = 0;
+    assert(open_memstream(NULL, &size) == NULL);
+    assert(errno == EINVAL);
+    return 0;
+}
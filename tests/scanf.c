@@ -0,0 +1,55 @@
+#include <assert.h>
+#include <stdarg.h>
+#include <stdio.h>
+#include <string.h>
+
+static int scan_string(const char *s, const char *fmt, ...) {
+    va_list args;
+    va_start(args, fmt);
+    int ret = vsscanf(s, fmt, args);
+    va_end(args);
+    return ret;
+}
+
+static int scan_file(FILE *f, const char *fmt, ...) {
+    va_list args;
+    va_start(args, fmt);
+    int ret = vfscanf(f, fmt, args);
+    va_end(args);
+    return ret;
+}
+
+int main() {
+    int a = 0, b = 0;
+    char word[16] = {0};
+
+    assert(sscanf("12 34", "%d %d", &a, &b) == 2);
+    assert(a == 12 && b == 34);
+    assert(sscanf("x", "%d", &a) == 0);
+    assert(sscanf("", "%d", &a) == EOF);
+    assert(scan_string("key=value", "%[^=]=%s", word, word + 8) == 2);
+    assert(strcmp(word, "key") == 0);
+    assert(scan_string("   ", "%d", &a) == EOF);
+
+    // Each call on a stream continues where the previous one stopped
+    FILE *f = tmpfile();
+    assert(f);
+    assert(fputs("7 abc\n8 end", f) >= 0);
+    rewind(f);
+    assert(fscanf(f, "%d", &a) == 1);
+    assert(a == 7);
+    assert(scan_file(f, "%s", word) == 1);
+    assert(strcmp(word, "abc") == 0);
+    assert(fscanf(f, "%d %s", &b, word) == 2);
+    assert(b == 8 && strcmp(word, "end") == 0);
+    assert(fscanf(f, "%d", &a) == EOF);
+    assert(feof(f));
+
+    // A mismatch leaves the byte it stopped at in the stream
+    rewind(f);
+    assert(fscanf(f, "%s", word) == 1);
+    assert(fscanf(f, "%d", &a) == 0);
+    assert(fgetc(f) == 'a');
+    assert(fclose(f) == 0);
+    return 0;
+}
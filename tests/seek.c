@@ -0,0 +1,51 @@
+#include <assert.h>
+#include <stdio.h>
+#include <string.h>
+#include <unistd.h>
+
+int main() {
+    FILE *f = tmpfile();
+    assert(f);
+    assert(fputs("0123456789", f) >= 0);
+
+    assert(fseek(f, 0, SEEK_END) == 0);
+    assert(ftell(f) == 10);
+    assert(fseek(f, -5, SEEK_CUR) == 0);
+    assert(ftell(f) == 5);
+    assert(fgetc(f) == '5');
+    assert(ftell(f) == 6);
+
+    // Reading after seeking from the end moves forward and reaches the end of the file
+    char buf[16] = {0};
+    assert(fseek(f, -3, SEEK_END) == 0);
+    assert(fread(buf, 1, sizeof(buf), f) == 3);
+    assert(memcmp(buf, "789", 3) == 0);
+    assert(feof(f));
+    assert(fgetc(f) == EOF);
+
+    assert(fseek(f, -11, SEEK_END) == -1);
+    assert(fseek(f, -1, SEEK_SET) == -1);
+
+    fpos_t pos;
+    assert(fseek(f, 2, SEEK_SET) == 0);
+    assert(fgetpos(f, &pos) == 0);
+    assert(fgetc(f) == '2');
+    assert(fsetpos(f, &pos) == 0);
+    assert(fgetc(f) == '2');
+
+    rewind(f);
+    assert(ftell(f) == 0);
+    assert(fgetc(f) == '0');
+
+    // The stream shares its position with its descriptor
+    int fd = fileno(f);
+    assert(fd >= 0);
+    assert(lseek(fd, 0, SEEK_END) == 10);
+    assert(lseek(fd, -4, SEEK_CUR) == 6);
+    assert(read(fd, buf, sizeof(buf)) == 4);
+    assert(memcmp(buf, "6789", 4) == 0);
+    assert(read(fd, buf, sizeof(buf)) == 0);
+
+    assert(fclose(f) == 0);
+    return 0;
+}
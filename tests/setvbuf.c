@@ -0,0 +1,67 @@
+#include <assert.h>
+#include <stdio.h>
+#include <string.h>
+
+/* Collects everything written to it, counting the writes */
+struct sink {
+    char data[256];
+    size_t len;
+    int calls;
+};
+
+static ssize_t sink_write(void *cookie, const char *buf, size_t size) {
+    struct sink *sink = cookie;
+    sink->calls++;
+    memcpy(sink->data + sink->len, buf, size);
+    sink->len += size;
+    return size;
+}
+
+int main() {
+    cookie_io_functions_t io = {.write = sink_write};
+
+    // Writes are kept in the caller's buffer until it fills up
+    struct sink sink = {0};
+    char buffer[8];
+    FILE *f = fopencookie(&sink, "w", io);
+    assert(f);
+    assert(setvbuf(f, buffer, _IOFBF, sizeof(buffer)) == 0);
+    assert(fputs("abc", f) >= 0);
+    assert(sink.calls == 0);
+    assert(memcmp(buffer, "abc", 3) == 0);
+    assert(fputs("defghij", f) >= 0);
+    assert(sink.calls == 1);
+    assert(sink.len == 8);
+    assert(memcmp(sink.data, "abcdefgh", 8) == 0);
+    assert(fflush(f) == 0);
+    assert(sink.len == 10);
+    assert(memcmp(sink.data, "abcdefghij", 10) == 0);
+
+    // A caller's buffer needs a size
+    assert(setvbuf(f, buffer, _IOFBF, 0) == -1);
+    assert(setvbuf(f, NULL, 3, 0) == -1);
+    assert(fclose(f) == 0);
+
+    // setbuffer with a buffer is fully buffered, without one unbuffered
+    sink = (struct sink){0};
+    f = fopencookie(&sink, "w", io);
+    assert(f);
+    setbuffer(f, buffer, sizeof(buffer));
+    assert(fputs("x\n", f) >= 0);
+    assert(sink.calls == 0);
+    setbuffer(f, NULL, 0);
+    assert(sink.len == 2);
+    assert(fputc('y', f) == 'y');
+    assert(sink.len == 3);
+
+    // setlinebuf writes each line as soon as it ends
+    setlinebuf(f);
+    assert(fputs("ab", f) >= 0);
+    assert(sink.len == 3);
+    assert(fputs("c\nd", f) >= 0);
+    assert(sink.len == 7);
+    assert(memcmp(sink.data, "x\nyabc\n", 7) == 0);
+    assert(fclose(f) == 0);
+    assert(sink.len == 8);
+    return 0;
+}
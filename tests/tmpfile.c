@@ -0,0 +1,49 @@
+#include <assert.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <sys/stat.h>
+#include <unistd.h>
+
+int main() {
+    // Temporary files are created in a directory of their own, which can only be removed once it is empty again
+    // `P_tmpdir` may not exist yet
+    mkdir(P_tmpdir, 0700);
+    char dir[] = P_tmpdir "/testXXXXXX";
+    assert(mkdtemp(dir));
+    assert(setenv("TMPDIR", dir, 1) == 0);
+
+    // Temporary files are removed once closed
+    FILE *f = tmpfile();
+    assert(f);
+    assert(fputs("abc", f) >= 0);
+    rewind(f);
+    assert(fgetc(f) == 'a');
+    assert(rmdir(dir) != 0);
+    assert(fclose(f) == 0);
+
+    // Names returned by `tmpnam` are unique and nothing is created at them
+    char a[L_tmpnam], b[L_tmpnam];
+    assert(tmpnam(a));
+    assert(tmpnam(b));
+    FILE *fa = fopen(a, "wx");
+    assert(fa);
+    assert(!fopen(a, "wx"));
+    FILE *fb = fopen(b, "wx");
+    assert(fb);
+    assert(fclose(fa) == 0);
+    assert(fclose(fb) == 0);
+    assert(remove(a) == 0);
+    assert(remove(b) == 0);
+
+    // `mkstemp` creates the file and leaves removing it to the caller
+    char path[sizeof(dir) + 16];
+    assert(snprintf(path, sizeof(path), "%s/testXXXXXX", dir) > 0);
+    int fd = mkstemp(path);
+    assert(fd >= 0);
+    assert(close(fd) == 0);
+    assert(remove(path) == 0);
+
+    assert(unsetenv("TMPDIR") == 0);
+    assert(rmdir(dir) == 0);
+    return 0;
+}
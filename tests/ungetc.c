@@ -0,0 +1,40 @@
+#include <assert.h>
+#include <stdio.h>
+
+int main() {
+    FILE *f = tmpfile();
+    assert(f);
+    assert(fputs("abc", f) >= 0);
+    rewind(f);
+
+    assert(fgetc(f) == 'a');
+    assert(ungetc('x', f) == 'x');
+    assert(ftell(f) == 0);
+    assert(fgetc(f) == 'x');
+    assert(fgetc(f) == 'b');
+
+    // Bytes pushed back are read in the reverse order
+    assert(ungetc('1', f) == '1');
+    assert(ungetc('2', f) == '2');
+    assert(fgetc(f) == '2');
+    assert(fgetc(f) == '1');
+
+    assert(ungetc(EOF, f) == EOF);
+    assert(fgetc(f) == 'c');
+
+    // Pushing back clears the end of file indicator
+    assert(fgetc(f) == EOF);
+    assert(feof(f));
+    assert(ungetc('z', f) == 'z');
+    assert(!feof(f));
+    assert(fgetc(f) == 'z');
+    assert(fgetc(f) == EOF);
+
+    // Seeking discards the bytes pushed back
+    assert(ungetc('y', f) == 'y');
+    assert(fseek(f, 1, SEEK_SET) == 0);
+    assert(fgetc(f) == 'b');
+
+    assert(fclose(f) == 0);
+    return 0;
+}